@@ -1,144 +1,2908 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, symbol_short, vec, Env, Symbol, Vec, Address, Map};
+use soroban_sdk::{contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token, vec, BytesN, Env, Symbol, Vec, Address, String};
 
-const CAMPAIGN_CREATED: Symbol = symbol_short!("CAMPCREATED");
-const CONTRIBUTION_MADE: Symbol = symbol_short!("CONTRIBUTE");
+const CAMPAIGN_CREATED: Symbol = symbol_short!("CAMPCREAT");
+const CONTRIBUTION_MADE: Symbol = symbol_short!("CONTRIB");
+const REFUND_ISSUED: Symbol = symbol_short!("REFUND");
+const FUNDS_CLAIMED: Symbol = symbol_short!("CLAIMED");
+const MILESTONE_REACHED: Symbol = symbol_short!("MILESTONE");
+const TRANCHE_REQUESTED: Symbol = symbol_short!("TRANCHREQ");
+const TRANCHE_APPROVED: Symbol = symbol_short!("TRANCHAPP");
+const TRANCHE_RELEASED: Symbol = symbol_short!("TRANCHREL");
+const TRANCHES_STOPPED: Symbol = symbol_short!("TRANCHSTP");
+const FUNDS_RECLAIMED: Symbol = symbol_short!("RECLAIM");
+const REWARD_SELECTED: Symbol = symbol_short!("REWARD");
+const PLEDGE_WITHDRAWN: Symbol = symbol_short!("UNPLEDGE");
+const CAMPAIGN_CANCELLED: Symbol = symbol_short!("CANCELLED");
+const CAMPAIGN_UPDATED: Symbol = symbol_short!("CAMPUPDT");
+const OPTED_OUT: Symbol = symbol_short!("OPTOUT");
+const POOL_CREATED: Symbol = symbol_short!("POOLCREAT");
+const FUNDS_MATCHED: Symbol = symbol_short!("MATCHED");
+const POOL_RECLAIMED: Symbol = symbol_short!("POOLRECL");
+const ROUND_CREATED: Symbol = symbol_short!("ROUNDCRT");
+const ROUND_FUNDED: Symbol = symbol_short!("ROUNDFUND");
+const ROUND_CLOSED: Symbol = symbol_short!("ROUNDCLOS");
+const QF_ALLOCATED: Symbol = symbol_short!("QFALLOC");
+const RECEIPT_MINTED: Symbol = symbol_short!("RCPTMINT");
+const RECEIPT_TRANSFERRED: Symbol = symbol_short!("RCPTXFER");
+const RECEIPT_BURNED: Symbol = symbol_short!("RCPTBURN");
+const SUBSCRIBED: Symbol = symbol_short!("SUBSCRIBE");
+const INSTALLMENT_PAID: Symbol = symbol_short!("SUBPAID");
+const INSTALLMENT_MISSED: Symbol = symbol_short!("SUBMISSED");
+const SUBSCRIPTION_CANCELLED: Symbol = symbol_short!("SUBCANCEL");
+const SUBSCRIPTION_ENDED: Symbol = symbol_short!("SUBENDED");
+const METADATA_UPDATED: Symbol = symbol_short!("METAUPDT");
+
+const MAX_MILESTONES: u32 = 10;
+const MAX_TRANCHES: u32 = 10;
+const MAX_REWARD_TIERS: u32 = 10;
+// Each tier's backers are kept in one list, so its supply is bounded
+const MAX_TIER_QUANTITY: u32 = 100;
+const MAX_ACCEPTED_TOKENS: u32 = 5;
+const MAX_PAGE_SIZE: u32 = 50;
+// Length of each campaign's leaderboard, kept up to date as contributions change
+const MAX_TOP_BACKERS: u32 = 20;
+// Campaigns read by one filtered listing call, however few of them match
+const MAX_SCAN_SIZE: u32 = 100;
+// Titles are kept short on-chain; the full description lives off-chain under the content hash
+const MAX_TITLE_LENGTH: u32 = 64;
+// Ceiling on the platform fee, whether set globally or per campaign
+const MAX_FEE_BPS: u32 = 1_000;
+const MAX_POOL_CAMPAIGNS: u32 = 20;
+const MAX_ROUND_CAMPAIGNS: u32 = 20;
+const BPS_DENOMINATOR: u32 = 10_000;
+// How far past its original deadline a campaign may be extended unless the admin configures otherwise
+const DEFAULT_MAX_EXTENSION: u64 = 30 * 24 * 60 * 60;
+// How long contributors have to opt out after a campaign's terms change
+const OPT_OUT_WINDOW: u64 = 3 * 24 * 60 * 60;
+// Consecutive failed installments after which a subscription is cancelled
+const MAX_MISSED_PAYMENTS: u32 = 3;
+
+// Contract specs allow at most 50 error cases, so new failures should reuse the closest existing one
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    NotFound = 4,
+    InvalidAmount = 5,
+    InvalidDeadline = 6,
+    DeadlinePassed = 7,
+    DeadlineNotPassed = 8,
+    TargetReached = 9,
+    TargetNotReached = 10,
+    AlreadyClaimed = 11,
+    NothingToRefund = 12,
+    InvalidTranches = 13,
+    ContributionsStarted = 14,
+    TranchedPayout = 15,
+    InvalidTrancheRequest = 16,
+    TrancheNotApproved = 17,
+    AlreadyVoted = 18,
+    NotContributor = 19,
+    TranchesStopped = 20,
+    TranchesNotStopped = 21,
+    InvalidRewardTiers = 22,
+    InvalidTier = 23,
+    BelowTierMinimum = 24,
+    TierSoldOut = 25,
+    UnpledgeDisabled = 26,
+    ExceedsContribution = 27,
+    CampaignCancelled = 28,
+    TargetBelowRaised = 29,
+    ExtensionTooLong = 30,
+    OptOutClosed = 31,
+    TermsAccepted = 32,
+    InvalidTokens = 33,
+    TokenNotAccepted = 34,
+    InvalidRules = 35,
+    BelowMinimum = 36,
+    ExceedsContributorMax = 37,
+    HardCapReached = 38,
+    NotAllowlisted = 39,
+    NotVerified = 40,
+    InvalidFee = 41,
+    InvalidPool = 42,
+    CampaignAlreadyMatched = 43,
+    InvalidRound = 44,
+    RoundClosed = 45,
+    ReceiverHasPosition = 46,
+    InvalidCampaign = 47,
+    InvalidPeriod = 48,
+    SubscriptionInactive = 49,
+    TallyIncomplete = 50,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Initialized,
+    Token,
+    Admin,
+    CampaignCount,
+    Campaign(u32),
+    Contribution(u32, Address),
+    Milestones(u32),
+    Tranches(u32),
+    TrancheApproval(u32, Address),
+    StopVote(u32, Address),
+    RewardTiers(u32),
+    TierBackers(u32, u32),
+    MaxExtension,
+    AssetUnit(Address),
+    PledgeTerms(u32, Address),
+    AssetContribution(u32, Address, Address),
+    AssetRaised(u32, Address),
+    CreatorCampaignCount(Address),
+    CreatorCampaign(Address, u32),
+    ContributorCount(u32),
+    Contributor(u32, u32),
+    TopBackers(u32),
+    RootSum(u32, Address),
+    Rules(u32),
+    Allowlisted(u32, Address),
+    FeeConfig,
+    FeeOverride(u32),
+    PoolCount,
+    Pool(u32),
+    CampaignPool(u32),
+    PoolMatched(u32),
+    MatchedContribution(u32, Address),
+    RoundCount,
+    Round(u32),
+    RoundAllocations(u32),
+    RoundTally(u32),
+    ReceiptCount,
+    Receipt(u64),
+    ReceiptOf(u32, Address),
+    SubscriptionCount,
+    Subscription(u32),
+    MetadataVersion(u32, u32),
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CampaignStatus {
+    // Accepting contributions until the deadline
+    Active,
+    // Deadline passed with the funding mode's threshold reached, awaiting the creator's claim
+    Successful,
+    // Deadline passed below the threshold, contributors can refund
+    Failed,
+    // Funds paid out to the creator
+    Claimed,
+    // Stopped by the creator or admin, every contributor can refund
+    Cancelled,
+}
+
+// What a campaign has to raise by its deadline for the creator to be paid
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FundingMode {
+    // The full target, otherwise every contributor is refunded
+    AllOrNothing,
+    // Whatever was raised goes to the creator, so contributions are only refundable on cancellation
+    KeepItAll,
+    // At least this percentage (1-100) of the target
+    MinimumPercent(u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Campaign {
+    pub creator: Address,
+    pub title: String,
+    // Digest of the off-chain description (e.g. an IPFS CID), bumped to a new version on every update
+    pub content_hash: BytesN<32>,
+    pub metadata_version: u32,
+    pub target_amount: i128,
+    pub deadline: u64,
+    pub amount_raised: i128,
+    pub status: CampaignStatus,
+    pub allow_unpledge: bool,
+    pub original_deadline: u64,
+    // Bumped on every update. Contributors who pledged under older terms may opt out until `opt_out_until`.
+    pub terms_version: u32,
+    pub opt_out_until: u64,
+    // Asset contracts the campaign takes contributions in. Amounts are counted towards the
+    // target at face value, so several assets are only accepted if the admin registered them
+    // under the same unit (e.g. USD stablecoins).
+    pub accepted_tokens: Vec<Address>,
+    pub funding_mode: FundingMode,
+}
+
+// One entry in a campaign's metadata history, so auditors can tell which description was live when
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataVersion {
+    pub version: u32,
+    pub title: String,
+    pub content_hash: BytesN<32>,
+    pub updated_at: u64,
+}
+
+// Optional settings supplied when a campaign is created
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CampaignOptions {
+    pub milestones: Vec<Milestone>,
+    pub reward_tiers: Vec<RewardTier>,
+    // Whether contributors may withdraw their pledge before the deadline
+    pub allow_unpledge: bool,
+    // Defaults to the contract's token when empty
+    pub accepted_tokens: Vec<Address>,
+    pub rules: ContributionRules,
+    pub funding_mode: FundingMode,
+}
+
+// Limits enforced on every contribution to a campaign. Leave every field unset for an open campaign.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContributionRules {
+    pub min_contribution: Option<i128>,
+    pub max_per_contributor: Option<i128>,
+    // Total above which contributions are rejected. Must be at least the target.
+    pub hard_cap: Option<i128>,
+    // When set, only listed addresses may contribute. The creator can change the list later.
+    pub allowlist: Option<Vec<Address>>,
+    // Contract implementing `KycVerifier`, asked about every contributor
+    pub kyc_verifier: Option<Address>,
+}
+
+// Interface a KYC provider contract exposes to vouch for contributors
+#[contractclient(name = "KycVerifierClient")]
+pub trait KycVerifier {
+    fn is_verified(env: Env, address: Address) -> bool;
+}
+
+// Platform fee taken from creator payouts, in basis points of the amount paid out
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub fee_bps: u32,
+    pub treasury: Address,
+}
+
+// Sponsor budget that matches contributions to the eligible campaigns as they come in
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchPool {
+    pub sponsor: Address,
+    pub token: Address,
+    // Matched amount per unit contributed, in basis points. 10_000 matches one-for-one.
+    pub ratio_bps: u32,
+    // Most the pool will put into any single campaign
+    pub campaign_cap: i128,
+    pub campaigns: Vec<u32>,
+    // Deposited budget not yet drawn into a campaign
+    pub remaining: i128,
+    // Latest deadline among the eligible campaigns when the pool was created. Reclaiming also
+    // waits for any campaign whose deadline has since been extended.
+    pub expires_at: u64,
+}
+
+// Quadratic funding round. The matching pool is split across the round's campaigns in
+// proportion to (sum of sqrt(contribution))^2 over each campaign's contributors.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Round {
+    pub operator: Address,
+    pub token: Address,
+    pub campaigns: Vec<u32>,
+    pub closes_at: u64,
+    // Sybil resistance: when set, only contributors this `KycVerifier` vouches for are counted
+    pub verifier: Option<Address>,
+    pub pool: i128,
+    pub closed: bool,
+}
+
+// Progress of counting a verified round's contributors, which `tally_round` does in pages
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundTally {
+    // Where the next page resumes: a position in the round's campaigns and in that campaign's contributors
+    pub campaign_position: u32,
+    pub contributor_position: u32,
+    // Summed square roots of verified contributions, one per campaign reached so far
+    pub root_sums: Vec<i128>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundAllocation {
+    pub campaign_index: u32,
+    pub score: i128,
+    pub amount: i128,
+}
+
+// A recurring pledge. Installments are pulled from the subscriber's allowance to this contract.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Subscription {
+    pub subscriber: Address,
+    pub campaign_index: u32,
+    pub token: Address,
+    pub amount: i128,
+    pub period: u64,
+    pub next_payment: u64,
+    pub installments_paid: u32,
+    // Consecutive failures, reset by every successful installment
+    pub missed_payments: u32,
+    pub active: bool,
+}
+
+// Non-fungible proof of backing. One receipt represents an address's whole position in a
+// campaign, and whoever holds it owns that position's refund claim.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Receipt {
+    pub id: u64,
+    pub campaign_index: u32,
+    pub owner: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReceiptMetadata {
+    pub id: u64,
+    pub campaign_index: u32,
+    pub campaign_title: String,
+    pub content_hash: BytesN<32>,
+    pub owner: Address,
+    pub amount: i128,
+}
+
+// A campaign returned from the listing queries along with its index
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CampaignEntry {
+    pub index: u32,
+    pub campaign: Campaign,
+}
+
+// One page of a filtered listing. `next_start` is the index to continue scanning from, or
+// `None` once every campaign has been read.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CampaignPage {
+    pub entries: Vec<CampaignEntry>,
+    pub next_start: Option<u32>,
+}
+
+// A contributor and their current total contribution to a campaign
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Backer {
+    pub address: Address,
+    pub amount: i128,
+}
+
+// An amount held in one asset
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetAmount {
+    pub token: Address,
+    pub amount: i128,
+}
+
+// A funding tier such as the base goal or a stretch target
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Milestone {
+    pub amount: i128,
+    pub label: String,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MilestoneState {
+    pub amount: i128,
+    pub label: String,
+    pub reached: bool,
+}
+
+// A backer perk offered for contributions of at least `min_amount`, limited to `quantity` backers
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardTier {
+    pub min_amount: i128,
+    pub quantity: u32,
+    pub description: String,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RewardTierState {
+    pub min_amount: i128,
+    pub quantity: u32,
+    pub remaining: u32,
+    pub description: String,
+}
+
+// Staged payout of a successful campaign. Each tranche is a share of the raised
+// total in basis points, released by the arbiter or a contributor-weighted majority.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrancheSchedule {
+    pub tranche_bps: Vec<u32>,
+    pub arbiter: Option<Address>,
+    pub released_count: u32,
+    pub released_amount: i128,
+    pub requested: bool,
+    // Incremented on every request so approvals from earlier tranches do not carry over
+    pub approval_round: u32,
+    pub approval_weight: i128,
+    pub stop_weight: i128,
+    pub stopped: bool,
+}
+
+// Resolve an active campaign into its settled status once the deadline has passed
+fn current_status(env: &Env, campaign: &Campaign) -> CampaignStatus {
+    if campaign.status != CampaignStatus::Active || env.ledger().timestamp() < campaign.deadline {
+        campaign.status
+    } else if campaign.amount_raised >= success_threshold(campaign) {
+        CampaignStatus::Successful
+    } else {
+        CampaignStatus::Failed
+    }
+}
+
+// Amount a campaign must have raised at its deadline to succeed
+fn success_threshold(campaign: &Campaign) -> i128 {
+    match campaign.funding_mode {
+        FundingMode::AllOrNothing => campaign.target_amount,
+        // Nothing raised still counts as failed so there is no empty payout to settle
+        FundingMode::KeepItAll => 1,
+        FundingMode::MinimumPercent(percent) => {
+            (campaign.target_amount * percent as i128 + 99) / 100
+        }
+    }
+}
+
+// Record the campaign's current title and content hash as its latest metadata version.
+// Each version is kept under its own key.
+fn push_metadata_version(env: &Env, campaign_index: u32, campaign: &Campaign) {
+    env.storage().persistent().set(
+        &DataKey::MetadataVersion(campaign_index, campaign.metadata_version),
+        &MetadataVersion {
+            version: campaign.metadata_version,
+            title: campaign.title.clone(),
+            content_hash: campaign.content_hash.clone(),
+            updated_at: env.ledger().timestamp(),
+        },
+    );
+}
+
+fn check_initialized(env: &Env) -> Result<(), Error> {
+    if env.storage().persistent().has(&DataKey::Initialized) {
+        Ok(())
+    } else {
+        Err(Error::NotInitialized)
+    }
+}
+
+fn campaign_count(env: &Env) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CampaignCount)
+        .unwrap_or(0)
+}
+
+// A creator's campaigns are numbered in creation order, each under its own key
+fn creator_campaign_count(env: &Env, creator: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::CreatorCampaignCount(creator.clone()))
+        .unwrap_or(0)
+}
+
+// Load a campaign for a read query, with its status resolved as of now
+fn campaign_entry(env: &Env, campaign_index: u32) -> Result<CampaignEntry, Error> {
+    let mut campaign = load_campaign(env, campaign_index)?;
+    campaign.status = current_status(env, &campaign);
+    Ok(CampaignEntry { index: campaign_index, campaign })
+}
+
+// Scan campaigns from `start` and collect up to `limit` that match the filter. At most
+// `MAX_SCAN_SIZE` campaigns are read, so a sparse filter may return a short page with more to come.
+fn scan_campaigns(
+    env: &Env,
+    start: u32,
+    limit: u32,
+    filter: impl Fn(&Campaign) -> bool,
+) -> Result<CampaignPage, Error> {
+    let limit = limit.min(MAX_PAGE_SIZE);
+    let count = campaign_count(env);
+    let end = count.min(start.saturating_add(MAX_SCAN_SIZE));
+    let mut entries = Vec::new(env);
+    let mut campaign_index = start;
+    while campaign_index < end && entries.len() < limit {
+        let entry = campaign_entry(env, campaign_index)?;
+        if filter(&entry.campaign) {
+            entries.push_back(entry);
+        }
+        campaign_index += 1;
+    }
+    let next_start = if campaign_index < count { Some(campaign_index) } else { None };
+    Ok(CampaignPage { entries, next_start })
+}
+
+fn load_admin(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)
+}
+
+fn load_fee_config(env: &Env) -> Option<FeeConfig> {
+    env.storage().persistent().get(&DataKey::FeeConfig)
+}
+
+// The fee rate charged on a campaign, with any per-campaign override applied
+fn campaign_fee_bps(env: &Env, campaign_index: u32) -> u32 {
+    match load_fee_config(env) {
+        Some(config) => env
+            .storage()
+            .persistent()
+            .get(&DataKey::FeeOverride(campaign_index))
+            .unwrap_or(config.fee_bps),
+        None => 0,
+    }
+}
+
+// Pay a creator out of escrow, sending the platform fee to the treasury. Returns the fee taken.
+fn pay_creator(env: &Env, campaign_index: u32, token: &Address, creator: &Address, amount: i128) -> i128 {
+    let client = token::Client::new(env, token);
+    let mut fee: i128 = 0;
+    if let Some(config) = load_fee_config(env) {
+        fee = amount * campaign_fee_bps(env, campaign_index) as i128 / BPS_DENOMINATOR as i128;
+        if fee > 0 {
+            client.transfer(&env.current_contract_address(), &config.treasury, &fee);
+        }
+    }
+    client.transfer(&env.current_contract_address(), creator, &(amount - fee));
+    fee
+}
+
+fn load_pool(env: &Env, pool_id: u32) -> Result<MatchPool, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Pool(pool_id))
+        .ok_or(Error::NotFound)
+}
+
+fn save_pool(env: &Env, pool_id: u32, pool: &MatchPool) {
+    env.storage().persistent().set(&DataKey::Pool(pool_id), pool);
+}
+
+fn load_pool_matched(env: &Env, campaign_index: u32) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PoolMatched(campaign_index))
+        .unwrap_or(0)
+}
+
+fn load_matched_contribution(env: &Env, campaign_index: u32, contributor: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::MatchedContribution(campaign_index, contributor.clone()))
+        .unwrap_or(0)
+}
+
+// The pool matching a campaign, if it matches contributions in this token
+fn matching_pool(env: &Env, campaign_index: u32, token: &Address) -> Option<(u32, MatchPool)> {
+    let pool_id: u32 = env.storage().persistent().get(&DataKey::CampaignPool(campaign_index))?;
+    let pool = load_pool(env, pool_id).ok()?;
+    if pool.token == *token {
+        Some((pool_id, pool))
+    } else {
+        None
+    }
+}
+
+// Contributions that carry a vote on tranches. Sponsor matches count towards the amount raised
+// but have no voter behind them, so they are left out of majorities.
+fn backer_weight(env: &Env, campaign_index: u32, campaign: &Campaign) -> i128 {
+    campaign.amount_raised - load_pool_matched(env, campaign_index)
+}
+
+// Move matched funds from a pool into a campaign, crediting the contributor whose pledge
+// earned them. A negative amount returns funds to the pool.
+fn move_match(
+    env: &Env,
+    campaign_index: u32,
+    contributor: &Address,
+    pool_id: u32,
+    pool: &mut MatchPool,
+    amount: i128,
+) {
+    pool.remaining -= amount;
+    save_pool(env, pool_id, pool);
+    env.storage()
+        .persistent()
+        .set(&DataKey::PoolMatched(campaign_index), &(load_pool_matched(env, campaign_index) + amount));
+    env.storage().persistent().set(
+        &DataKey::MatchedContribution(campaign_index, contributor.clone()),
+        &(load_matched_contribution(env, campaign_index, contributor) + amount),
+    );
+}
+
+// Draw sponsor funds to match a contribution that has already been added to `campaign`.
+// Returns the matched amount, which the caller adds to the campaign's totals.
+fn draw_match(
+    env: &Env,
+    campaign_index: u32,
+    campaign: &Campaign,
+    contributor: &Address,
+    token: &Address,
+    amount: i128,
+) -> i128 {
+    let (pool_id, mut pool) = match matching_pool(env, campaign_index, token) {
+        Some(found) => found,
+        None => return 0,
+    };
+
+    let mut matched = (amount * pool.ratio_bps as i128 / BPS_DENOMINATOR as i128)
+        .min(pool.remaining)
+        .min(pool.campaign_cap - load_pool_matched(env, campaign_index));
+    if let Some(cap) = load_rules(env, campaign_index).and_then(|rules| rules.hard_cap) {
+        matched = matched.min(cap - campaign.amount_raised);
+    }
+    if matched <= 0 {
+        return 0;
+    }
+
+    move_match(env, campaign_index, contributor, pool_id, &mut pool, matched);
+    env.events().publish(
+        (FUNDS_MATCHED, campaign_index),
+        (pool_id, contributor.clone(), matched),
+    );
+    matched
+}
+
+// Hand matched funds back to the pool when the pledge that earned them is withdrawn.
+// Returns the amount released, which the caller removes from the campaign's totals.
+fn release_match(env: &Env, campaign_index: u32, contributor: &Address, token: &Address, amount: i128) -> i128 {
+    let (pool_id, mut pool) = match matching_pool(env, campaign_index, token) {
+        Some(found) => found,
+        None => return 0,
+    };
+
+    let released = (amount * pool.ratio_bps as i128 / BPS_DENOMINATOR as i128)
+        .min(load_matched_contribution(env, campaign_index, contributor));
+    if released <= 0 {
+        return 0;
+    }
+    move_match(env, campaign_index, contributor, pool_id, &mut pool, -released);
+    released
+}
+
+fn load_round(env: &Env, round_id: u32) -> Result<Round, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Round(round_id))
+        .ok_or(Error::NotFound)
+}
+
+fn save_round(env: &Env, round_id: u32, round: &Round) {
+    env.storage().persistent().set(&DataKey::Round(round_id), round);
+}
+
+// Integer square root, rounded down
+fn isqrt(value: i128) -> i128 {
+    if value <= 0 {
+        return 0;
+    }
+    let mut root = value;
+    let mut next = (value + 1) / 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root
+}
+
+// value * numerator / denominator, dropping low bits of the ratio when the product would overflow
+fn mul_div(value: i128, mut numerator: i128, mut denominator: i128) -> i128 {
+    loop {
+        if let Some(product) = value.checked_mul(numerator) {
+            return product / denominator;
+        }
+        numerator >>= 1;
+        denominator >>= 1;
+    }
+}
+
+// Only campaigns that met their funding threshold keep their backers' money, so only they score
+fn scores_in_round(env: &Env, campaign: &Campaign) -> bool {
+    matches!(current_status(env, campaign), CampaignStatus::Successful | CampaignStatus::Claimed)
+}
+
+// A round can be settled once its time is up and none of its campaigns can still take or return
+// contributions
+fn check_round_over(env: &Env, round: &Round) -> Result<(), Error> {
+    if round.closed {
+        return Err(Error::RoundClosed);
+    }
+    let now = env.ledger().timestamp();
+    if now < round.closes_at {
+        return Err(Error::DeadlineNotPassed);
+    }
+    // A campaign extended past the close still has contributions that may be withdrawn
+    for campaign_index in round.campaigns.iter() {
+        let campaign = load_campaign(env, campaign_index)?;
+        if campaign.status != CampaignStatus::Cancelled && now < campaign.deadline {
+            return Err(Error::DeadlineNotPassed);
+        }
+    }
+    Ok(())
+}
+
+fn load_round_tally(env: &Env, round_id: u32) -> RoundTally {
+    env.storage()
+        .persistent()
+        .get(&DataKey::RoundTally(round_id))
+        .unwrap_or_else(|| RoundTally { campaign_position: 0, contributor_position: 0, root_sums: Vec::new(env) })
+}
+
+// A campaign's quadratic funding score: the square of the summed square roots of each counted
+// contributor's contribution in the round's token. Verified rounds read the sums `tally_round` built.
+fn round_score(
+    env: &Env,
+    round_id: u32,
+    round: &Round,
+    position: u32,
+    campaign_index: u32,
+) -> Result<i128, Error> {
+    let campaign = load_campaign(env, campaign_index)?;
+    if !scores_in_round(env, &campaign) {
+        return Ok(0);
+    }
+
+    let sum_of_roots = match round.verifier {
+        Some(_) => load_round_tally(env, round_id).root_sums.get(position).unwrap_or(0),
+        None => load_root_sum(env, campaign_index, &round.token),
+    };
+    Ok(sum_of_roots * sum_of_roots)
+}
+
+fn load_campaign(env: &Env, campaign_index: u32) -> Result<Campaign, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Campaign(campaign_index))
+        .ok_or(Error::NotFound)
+}
+
+fn save_campaign(env: &Env, campaign_index: u32, campaign: &Campaign) {
+    env.storage().persistent().set(&DataKey::Campaign(campaign_index), campaign);
+}
+
+fn load_contribution(env: &Env, campaign_index: u32, contributor: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Contribution(campaign_index, contributor.clone()))
+        .unwrap_or(0)
+}
+
+fn save_contribution(env: &Env, campaign_index: u32, contributor: &Address, amount: i128) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Contribution(campaign_index, contributor.clone()), &amount);
+    update_top_backers(env, campaign_index, contributor, amount);
+}
+
+// Contributors are numbered in order of their first contribution, each under its own key
+fn contributor_count(env: &Env, campaign_index: u32) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ContributorCount(campaign_index))
+        .unwrap_or(0)
+}
+
+fn load_contributor(env: &Env, campaign_index: u32, position: u32) -> Address {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Contributor(campaign_index, position))
+        .unwrap()
+}
+
+fn add_contributor(env: &Env, campaign_index: u32, contributor: &Address) {
+    let position = contributor_count(env, campaign_index);
+    env.storage()
+        .persistent()
+        .set(&DataKey::Contributor(campaign_index, position), contributor);
+    env.storage()
+        .persistent()
+        .set(&DataKey::ContributorCount(campaign_index), &(position + 1));
+}
+
+fn load_top_backers(env: &Env, campaign_index: u32) -> Vec<Backer> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TopBackers(campaign_index))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+// Move a backer to their place on the leaderboard for their new total. Backers who reach an
+// amount later rank behind those already at it. Only the backer whose total changed is ranked, so
+// when someone on the list withdraws, backers below the list wait for `refresh_top_backers`.
+fn update_top_backers(env: &Env, campaign_index: u32, contributor: &Address, amount: i128) {
+    let mut top = load_top_backers(env, campaign_index);
+    let mut changed = false;
+    if let Some(position) = top.iter().position(|backer| backer.address == *contributor) {
+        if top.get(position as u32).unwrap().amount == amount {
+            return;
+        }
+        top.remove(position as u32);
+        changed = true;
+    }
+    if amount > 0 {
+        let mut position = top.len();
+        while position > 0 && top.get(position - 1).unwrap().amount < amount {
+            position -= 1;
+        }
+        if position < MAX_TOP_BACKERS {
+            top.insert(position, Backer { address: contributor.clone(), amount });
+            if top.len() > MAX_TOP_BACKERS {
+                top.pop_back();
+            }
+            changed = true;
+        }
+    }
+    if changed {
+        env.storage().persistent().set(&DataKey::TopBackers(campaign_index), &top);
+    }
+}
+
+// Sum of the square roots of every contribution a campaign received in one asset, kept
+// current so quadratic funding rounds don't have to read each contributor
+fn load_root_sum(env: &Env, campaign_index: u32, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::RootSum(campaign_index, token.clone()))
+        .unwrap_or(0)
+}
+
+fn load_rules(env: &Env, campaign_index: u32) -> Option<ContributionRules> {
+    env.storage().persistent().get(&DataKey::Rules(campaign_index))
+}
+
+fn has_rules(rules: &ContributionRules) -> bool {
+    rules.min_contribution.is_some()
+        || rules.max_per_contributor.is_some()
+        || rules.hard_cap.is_some()
+        || rules.allowlist.is_some()
+        || rules.kyc_verifier.is_some()
+}
+
+fn validate_rules(rules: &ContributionRules, target_amount: i128) -> Result<(), Error> {
+    let min_contribution = rules.min_contribution.unwrap_or(0);
+    if min_contribution < 0 {
+        return Err(Error::InvalidRules);
+    }
+    if rules.max_per_contributor.is_some_and(|max| max <= 0 || max < min_contribution) {
+        return Err(Error::InvalidRules);
+    }
+    if rules.hard_cap.is_some_and(|cap| cap < target_amount) {
+        return Err(Error::InvalidRules);
+    }
+    Ok(())
+}
+
+// Check that an address may hold a position of `position` in total under the campaign's rules
+fn check_holder(
+    env: &Env,
+    campaign_index: u32,
+    rules: &ContributionRules,
+    holder: &Address,
+    position: i128,
+) -> Result<(), Error> {
+    if rules.max_per_contributor.is_some_and(|max| position > max) {
+        return Err(Error::ExceedsContributorMax);
+    }
+    if rules.allowlist.is_some()
+        && !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Allowlisted(campaign_index, holder.clone()))
+    {
+        return Err(Error::NotAllowlisted);
+    }
+    if let Some(verifier) = &rules.kyc_verifier
+        && !KycVerifierClient::new(env, verifier).is_verified(holder)
+    {
+        return Err(Error::NotVerified);
+    }
+    Ok(())
+}
+
+// Check a contribution against the campaign's rules before any funds move
+fn check_rules(
+    env: &Env,
+    campaign_index: u32,
+    campaign: &Campaign,
+    contributor: &Address,
+    amount: i128,
+) -> Result<(), Error> {
+    let rules = match load_rules(env, campaign_index) {
+        Some(rules) => rules,
+        None => return Ok(()),
+    };
+
+    if rules.min_contribution.is_some_and(|min| amount < min) {
+        return Err(Error::BelowMinimum);
+    }
+    if rules.hard_cap.is_some_and(|cap| campaign.amount_raised + amount > cap) {
+        return Err(Error::HardCapReached);
+    }
+    let position = load_contribution(env, campaign_index, contributor) + amount;
+    check_holder(env, campaign_index, &rules, contributor, position)
+}
+
+fn set_allowlisted(env: &Env, campaign_index: u32, addresses: &Vec<Address>, allowed: bool) {
+    for address in addresses.iter() {
+        let key = DataKey::Allowlisted(campaign_index, address);
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+}
+
+fn load_asset_contribution(env: &Env, campaign_index: u32, contributor: &Address, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::AssetContribution(campaign_index, contributor.clone(), token.clone()))
+        .unwrap_or(0)
+}
+
+fn save_asset_contribution(env: &Env, campaign_index: u32, contributor: &Address, token: &Address, amount: i128) {
+    let previous = load_asset_contribution(env, campaign_index, contributor, token);
+    env.storage().persistent().set(
+        &DataKey::AssetContribution(campaign_index, contributor.clone(), token.clone()),
+        &amount,
+    );
+    if previous != amount {
+        let root_sum = load_root_sum(env, campaign_index, token) + isqrt(amount) - isqrt(previous);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RootSum(campaign_index, token.clone()), &root_sum);
+    }
+}
+
+fn load_asset_raised(env: &Env, campaign_index: u32, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::AssetRaised(campaign_index, token.clone()))
+        .unwrap_or(0)
+}
+
+fn save_asset_raised(env: &Env, campaign_index: u32, token: &Address, amount: i128) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::AssetRaised(campaign_index, token.clone()), &amount);
+}
+
+fn load_asset_unit(env: &Env, token: &Address) -> Option<Symbol> {
+    env.storage().persistent().get(&DataKey::AssetUnit(token.clone()))
+}
+
+// A campaign taking several assets adds them up at face value, so they must all be registered
+// under one unit
+fn validate_accepted_tokens(env: &Env, accepted_tokens: &Vec<Address>) -> Result<(), Error> {
+    if accepted_tokens.len() > MAX_ACCEPTED_TOKENS {
+        return Err(Error::InvalidTokens);
+    }
+    for (token_index, token) in accepted_tokens.iter().enumerate() {
+        if accepted_tokens.first_index_of(&token) != Some(token_index as u32) {
+            return Err(Error::InvalidTokens);
+        }
+    }
+    if accepted_tokens.len() > 1 {
+        let unit = load_asset_unit(env, &accepted_tokens.get(0).unwrap());
+        if unit.is_none() {
+            return Err(Error::InvalidTokens);
+        }
+        for token in accepted_tokens.iter() {
+            if load_asset_unit(env, &token) != unit {
+                return Err(Error::InvalidTokens);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn load_subscription(env: &Env, subscription_id: u32) -> Result<Subscription, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Subscription(subscription_id))
+        .ok_or(Error::NotFound)
+}
+
+fn save_subscription(env: &Env, subscription_id: u32, subscription: &Subscription) {
+    env.storage().persistent().set(&DataKey::Subscription(subscription_id), subscription);
+}
+
+// Try to collect one due installment, returning whether it was paid. A missing allowance or
+// balance, or an installment the campaign's rules would reject, counts as a missed payment.
+fn collect_installment(env: &Env, subscription: &Subscription, campaign: Campaign) -> bool {
+    let campaign_index = subscription.campaign_index;
+    if check_rules(env, campaign_index, &campaign, &subscription.subscriber, subscription.amount).is_err() {
+        return false;
+    }
+    let contract = env.current_contract_address();
+    let paid = token::Client::new(env, &subscription.token).try_transfer_from(
+        &contract,
+        &subscription.subscriber,
+        &contract,
+        &subscription.amount,
+    );
+    if !matches!(paid, Ok(Ok(()))) {
+        return false;
+    }
+    let subscriber = &subscription.subscriber;
+    record_contribution(env, campaign_index, campaign, subscriber, &subscription.token, subscription.amount, None);
+    true
+}
+
+fn load_receipt(env: &Env, receipt_id: u64) -> Result<Receipt, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Receipt(receipt_id))
+        .ok_or(Error::NotFound)
+}
+
+// Issue a receipt the first time an address backs a campaign
+fn mint_receipt(env: &Env, campaign_index: u32, owner: &Address) {
+    let receipt_of = DataKey::ReceiptOf(campaign_index, owner.clone());
+    if env.storage().persistent().has(&receipt_of) {
+        return;
+    }
+    let id: u64 = env.storage().persistent().get(&DataKey::ReceiptCount).unwrap_or(0);
+    env.storage().persistent().set(
+        &DataKey::Receipt(id),
+        &Receipt { id, campaign_index, owner: owner.clone() },
+    );
+    env.storage().persistent().set(&receipt_of, &id);
+    env.storage().persistent().set(&DataKey::ReceiptCount, &(id + 1));
+
+    env.events().publish(
+        (RECEIPT_MINTED, owner.clone()),
+        (id, campaign_index),
+    );
+}
+
+// Destroy a receipt once its position has been paid back in full
+fn burn_receipt(env: &Env, campaign_index: u32, owner: &Address) {
+    let receipt_of = DataKey::ReceiptOf(campaign_index, owner.clone());
+    let id: u64 = match env.storage().persistent().get(&receipt_of) {
+        Some(id) => id,
+        None => return,
+    };
+    env.storage().persistent().remove(&receipt_of);
+    env.storage().persistent().remove(&DataKey::Receipt(id));
+
+    env.events().publish(
+        (RECEIPT_BURNED, owner.clone()),
+        (id, campaign_index),
+    );
+}
+
+// Re-key everything a backer holds in a campaign to a new address
+fn move_position(env: &Env, campaign_index: u32, campaign: &Campaign, from: &Address, to: &Address) {
+    let storage = env.storage().persistent();
+
+    save_contribution(env, campaign_index, to, load_contribution(env, campaign_index, from));
+    save_contribution(env, campaign_index, from, 0);
+    for token in campaign.accepted_tokens.iter() {
+        let amount = load_asset_contribution(env, campaign_index, from, &token);
+        save_asset_contribution(env, campaign_index, to, &token, amount);
+        save_asset_contribution(env, campaign_index, from, &token, 0);
+    }
+    add_contributor(env, campaign_index, to);
+
+    // Matched funds, accepted terms and votes follow the position so they can't be used twice
+    let matched = load_matched_contribution(env, campaign_index, from);
+    if matched > 0 {
+        storage.set(&DataKey::MatchedContribution(campaign_index, to.clone()), &matched);
+        storage.remove(&DataKey::MatchedContribution(campaign_index, from.clone()));
+    }
+    let keys = [
+        (DataKey::PledgeTerms(campaign_index, from.clone()), DataKey::PledgeTerms(campaign_index, to.clone())),
+        (DataKey::TrancheApproval(campaign_index, from.clone()), DataKey::TrancheApproval(campaign_index, to.clone())),
+        (DataKey::StopVote(campaign_index, from.clone()), DataKey::StopVote(campaign_index, to.clone())),
+    ];
+    for (from_key, to_key) in keys {
+        if let Some(value) = storage.get::<DataKey, soroban_sdk::Val>(&from_key) {
+            storage.set(&to_key, &value);
+        }
+    }
+    for tier_index in 0..load_reward_tiers(env, campaign_index).len() {
+        let mut backers = load_tier_backers(env, campaign_index, tier_index);
+        if let Some(position) = backers.first_index_of(from) {
+            backers.set(position, to.clone());
+            storage.set(&DataKey::TierBackers(campaign_index, tier_index), &backers);
+        }
+    }
+}
+
+// Pay a contributor's whole pledge back in each asset they used
+fn return_pledge(env: &Env, campaign_index: u32, campaign: &Campaign, contributor: &Address) -> Vec<AssetAmount> {
+    let mut paid = Vec::new(env);
+    for token in campaign.accepted_tokens.iter() {
+        let amount = load_asset_contribution(env, campaign_index, contributor, &token);
+        if amount > 0 {
+            save_asset_contribution(env, campaign_index, contributor, &token, 0);
+            token::Client::new(env, &token).transfer(&env.current_contract_address(), contributor, &amount);
+            paid.push_back(AssetAmount { token, amount });
+        }
+    }
+    save_contribution(env, campaign_index, contributor, 0);
+    burn_receipt(env, campaign_index, contributor);
+    paid
+}
+
+fn load_milestones(env: &Env, campaign_index: u32) -> Vec<Milestone> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Milestones(campaign_index))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+// Milestones must be positive and strictly increasing
+fn validate_milestones(milestones: &Vec<Milestone>) -> Result<(), Error> {
+    if milestones.len() > MAX_MILESTONES {
+        return Err(Error::InvalidCampaign);
+    }
+    let mut previous: i128 = 0;
+    for milestone in milestones.iter() {
+        if milestone.amount <= previous {
+            return Err(Error::InvalidCampaign);
+        }
+        previous = milestone.amount;
+    }
+    Ok(())
+}
+
+fn load_reward_tiers(env: &Env, campaign_index: u32) -> Vec<RewardTier> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::RewardTiers(campaign_index))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn load_tier_backers(env: &Env, campaign_index: u32, tier_index: u32) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TierBackers(campaign_index, tier_index))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn validate_reward_tiers(reward_tiers: &Vec<RewardTier>) -> Result<(), Error> {
+    if reward_tiers.len() > MAX_REWARD_TIERS {
+        return Err(Error::InvalidRewardTiers);
+    }
+    for tier in reward_tiers.iter() {
+        if tier.min_amount <= 0 || tier.quantity == 0 || tier.quantity > MAX_TIER_QUANTITY {
+            return Err(Error::InvalidRewardTiers);
+        }
+    }
+    Ok(())
+}
+
+// Reserve one unit of a reward tier for the contributor
+fn select_reward_tier(
+    env: &Env,
+    campaign_index: u32,
+    tier_index: u32,
+    contributor: &Address,
+    amount: i128,
+) -> Result<(), Error> {
+    let tier = load_reward_tiers(env, campaign_index)
+        .get(tier_index)
+        .ok_or(Error::InvalidTier)?;
+    if amount < tier.min_amount {
+        return Err(Error::BelowTierMinimum);
+    }
+
+    let mut backers = load_tier_backers(env, campaign_index, tier_index);
+    if backers.len() >= tier.quantity {
+        return Err(Error::TierSoldOut);
+    }
+    if backers.contains(contributor) {
+        return Err(Error::InvalidTier);
+    }
+    backers.push_back(contributor.clone());
+    env.storage()
+        .persistent()
+        .set(&DataKey::TierBackers(campaign_index, tier_index), &backers);
+
+    Ok(())
+}
+
+// Give up any reward tier whose minimum the contributor no longer meets
+fn release_reward_tiers(env: &Env, campaign_index: u32, contributor: &Address, contribution: i128) {
+    for (tier_index, tier) in load_reward_tiers(env, campaign_index).iter().enumerate() {
+        if contribution >= tier.min_amount {
+            continue;
+        }
+        let mut backers = load_tier_backers(env, campaign_index, tier_index as u32);
+        if let Some(position) = backers.first_index_of(contributor) {
+            backers.remove(position);
+            env.storage()
+                .persistent()
+                .set(&DataKey::TierBackers(campaign_index, tier_index as u32), &backers);
+        }
+    }
+}
+
+fn load_tranches(env: &Env, campaign_index: u32) -> Option<TrancheSchedule> {
+    env.storage().persistent().get(&DataKey::Tranches(campaign_index))
+}
+
+fn save_tranches(env: &Env, campaign_index: u32, schedule: &TrancheSchedule) {
+    env.storage().persistent().set(&DataKey::Tranches(campaign_index), schedule);
+}
+
+// Tranches must be positive and add up to the whole raised amount
+fn validate_tranches(tranche_bps: &Vec<u32>) -> Result<(), Error> {
+    if tranche_bps.is_empty() || tranche_bps.len() > MAX_TRANCHES {
+        return Err(Error::InvalidTranches);
+    }
+    let mut total: u32 = 0;
+    for bps in tranche_bps.iter() {
+        if bps == 0 {
+            return Err(Error::InvalidTranches);
+        }
+        total += bps;
+    }
+    if total != BPS_DENOMINATOR {
+        return Err(Error::InvalidTranches);
+    }
+    Ok(())
+}
+
+// A tranche campaign can only be paid out once it has succeeded and while the schedule is running
+fn load_running_tranches(env: &Env, campaign: &Campaign, campaign_index: u32) -> Result<TrancheSchedule, Error> {
+    let schedule = load_tranches(env, campaign_index).ok_or(Error::NotFound)?;
+    if env.ledger().timestamp() < campaign.deadline {
+        return Err(Error::DeadlineNotPassed);
+    }
+    match current_status(env, campaign) {
+        CampaignStatus::Successful => {}
+        CampaignStatus::Claimed => return Err(Error::AlreadyClaimed),
+        CampaignStatus::Cancelled => return Err(Error::CampaignCancelled),
+        _ => return Err(Error::TargetNotReached),
+    }
+    if schedule.stopped {
+        return Err(Error::TranchesStopped);
+    }
+    Ok(schedule)
+}
+
+// How much of an asset the first `count` tranches pay out. The final tranche takes
+// whatever is left so rounding never strands funds.
+fn released_through(schedule: &TrancheSchedule, raised: i128, count: u32) -> i128 {
+    if count == schedule.tranche_bps.len() {
+        return raised;
+    }
+    let mut released: i128 = 0;
+    for bps in schedule.tranche_bps.iter().take(count as usize) {
+        released += raised * bps as i128 / BPS_DENOMINATOR as i128;
+    }
+    released
+}
+
+// Book escrowed funds against a campaign: totals, matching, the contributor's position and events
+fn record_contribution(
+    env: &Env,
+    campaign_index: u32,
+    mut campaign: Campaign,
+    contributor: &Address,
+    token: &Address,
+    amount: i128,
+    reward_tier: Option<u32>,
+) {
+    let previously_raised = campaign.amount_raised;
+    campaign.amount_raised += amount;
+    let matched = draw_match(env, campaign_index, &campaign, contributor, token, amount);
+    campaign.amount_raised += matched;
+    save_campaign(env, campaign_index, &campaign);
+    let asset_raised = load_asset_raised(env, campaign_index, token);
+    save_asset_raised(env, campaign_index, token, asset_raised + amount + matched);
+
+    // The contribution entry is never removed, so its absence marks a first-time contributor
+    if !env.storage().persistent().has(&DataKey::Contribution(campaign_index, contributor.clone())) {
+        add_contributor(env, campaign_index, contributor);
+    }
+    let current_contribution = load_contribution(env, campaign_index, contributor);
+    save_contribution(env, campaign_index, contributor, current_contribution + amount);
+    let asset_contribution = load_asset_contribution(env, campaign_index, contributor, token);
+    save_asset_contribution(env, campaign_index, contributor, token, asset_contribution + amount);
+    // Pledging again accepts the campaign's current terms
+    env.storage()
+        .persistent()
+        .set(&DataKey::PledgeTerms(campaign_index, contributor.clone()), &campaign.terms_version);
+    mint_receipt(env, campaign_index, contributor);
+
+    // The metadata version tells auditors which description the backer was shown
+    env.events().publish(
+        (CONTRIBUTION_MADE, contributor.clone()),
+        (campaign_index, token.clone(), amount, campaign.metadata_version),
+    );
+    if let Some(tier_index) = reward_tier {
+        env.events().publish(
+            (REWARD_SELECTED, contributor.clone()),
+            (campaign_index, tier_index),
+        );
+    }
+
+    // Announce every milestone this contribution pushed the campaign past
+    for (milestone_index, milestone) in load_milestones(env, campaign_index).iter().enumerate() {
+        if previously_raised < milestone.amount && campaign.amount_raised >= milestone.amount {
+            env.events().publish(
+                (MILESTONE_REACHED, campaign_index),
+                (milestone_index as u32, milestone.amount, milestone.label),
+            );
+        }
+    }
+}
 
 #[contract]
 pub struct CrowdfundingContract;
 
-#[contractimpl]
-impl CrowdfundingContract {
-    
-    pub fn initialize(env: Env) {
-        
-        env.storage().persistent().set(&symbol_short!("campaigns"), &Vec::new(&env));
-        env.storage().persistent().set(&symbol_short!("contributions"), &Map::new(&env));
+#[contractimpl]
+impl CrowdfundingContract {
+
+    pub fn initialize(env: Env, token_address: Address, admin: Address) -> Result<(), Error> {
+        if env.storage().persistent().has(&DataKey::Initialized) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        env.storage().persistent().set(&DataKey::Token, &token_address);
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::CampaignCount, &0u32);
+        env.storage().persistent().set(&DataKey::Initialized, &true);
+
+        Ok(())
+    }
+
+
+    pub fn create_campaign(
+        env: Env,
+        creator: Address,
+        title: String,
+        content_hash: BytesN<32>,
+        target_amount: i128,
+        deadline: u64,
+        options: CampaignOptions,
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let current_timestamp = env.ledger().timestamp();
+        if deadline <= current_timestamp {
+            return Err(Error::InvalidDeadline);
+        }
+        if target_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if title.len() > MAX_TITLE_LENGTH {
+            return Err(Error::InvalidCampaign);
+        }
+        validate_milestones(&options.milestones)?;
+        validate_reward_tiers(&options.reward_tiers)?;
+        validate_accepted_tokens(&env, &options.accepted_tokens)?;
+        validate_rules(&options.rules, target_amount)?;
+        if matches!(options.funding_mode, FundingMode::MinimumPercent(percent) if percent == 0 || percent > 100) {
+            return Err(Error::InvalidCampaign);
+        }
+        let accepted_tokens = if options.accepted_tokens.is_empty() {
+            let default_token: Address = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Token)
+                .ok_or(Error::NotInitialized)?;
+            vec![&env, default_token]
+        } else {
+            options.accepted_tokens
+        };
+
+        let campaign = Campaign {
+            creator: creator.clone(),
+            title: title.clone(),
+            content_hash: content_hash.clone(),
+            metadata_version: 0,
+            target_amount,
+            deadline,
+            amount_raised: 0,
+            status: CampaignStatus::Active,
+            allow_unpledge: options.allow_unpledge,
+            original_deadline: deadline,
+            terms_version: 0,
+            opt_out_until: 0,
+            accepted_tokens,
+            funding_mode: options.funding_mode,
+        };
+
+
+        let campaign_index = campaign_count(&env);
+        save_campaign(&env, campaign_index, &campaign);
+        push_metadata_version(&env, campaign_index, &campaign);
+        let creator_position = creator_campaign_count(&env, &creator);
+        env.storage()
+            .persistent()
+            .set(&DataKey::CreatorCampaign(creator.clone(), creator_position), &campaign_index);
+        env.storage()
+            .persistent()
+            .set(&DataKey::CreatorCampaignCount(creator.clone()), &(creator_position + 1));
+        if !options.milestones.is_empty() {
+            env.storage().persistent().set(&DataKey::Milestones(campaign_index), &options.milestones);
+        }
+        if !options.reward_tiers.is_empty() {
+            env.storage().persistent().set(&DataKey::RewardTiers(campaign_index), &options.reward_tiers);
+        }
+        if has_rules(&options.rules) {
+            let mut rules = options.rules;
+            // Allowlist entries are kept under their own keys; the stored rules only mark it as enabled
+            if let Some(allowlist) = rules.allowlist {
+                set_allowlisted(&env, campaign_index, &allowlist, true);
+                rules.allowlist = Some(Vec::new(&env));
+            }
+            env.storage().persistent().set(&DataKey::Rules(campaign_index), &rules);
+        }
+
+
+        env.storage().persistent().set(&DataKey::CampaignCount, &(campaign_index + 1));
+
+        env.events().publish(
+            (CAMPAIGN_CREATED, creator),
+            (campaign_index, title, content_hash, target_amount, deadline, options.funding_mode),
+        );
+
+        Ok(campaign_index)
+    }
+
+    pub fn contribute(
+        env: Env,
+        contributor: Address,
+        campaign_index: u32,
+        token: Address,
+        amount: i128,
+        reward_tier: Option<u32>,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+
+        let current_timestamp = env.ledger().timestamp();
+        if current_timestamp >= campaign.deadline {
+            return Err(Error::DeadlinePassed);
+        }
+        if !campaign.accepted_tokens.contains(&token) {
+            return Err(Error::TokenNotAccepted);
+        }
+        check_rules(&env, campaign_index, &campaign, &contributor, amount)?;
+
+        if let Some(tier_index) = reward_tier {
+            select_reward_tier(&env, campaign_index, tier_index, &contributor, amount)?;
+        }
+
+        // Pull the funds into escrow under the contract address
+        token::Client::new(&env, &token).transfer(&contributor, &env.current_contract_address(), &amount);
+        record_contribution(&env, campaign_index, campaign, &contributor, &token, amount, reward_tier);
+
+        Ok(())
+    }
+
+    // Withdraw part or all of a pledge in one asset before the deadline, if the campaign allows it
+    pub fn unpledge(
+        env: Env,
+        contributor: Address,
+        campaign_index: u32,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+        if !campaign.allow_unpledge {
+            return Err(Error::UnpledgeDisabled);
+        }
+        if env.ledger().timestamp() >= campaign.deadline {
+            return Err(Error::DeadlinePassed);
+        }
+
+        if !campaign.accepted_tokens.contains(&token) {
+            return Err(Error::TokenNotAccepted);
+        }
+
+        let asset_contribution = load_asset_contribution(&env, campaign_index, &contributor, &token);
+        if amount > asset_contribution {
+            return Err(Error::ExceedsContribution);
+        }
+
+        save_asset_contribution(&env, campaign_index, &contributor, &token, asset_contribution - amount);
+        let remaining = load_contribution(&env, campaign_index, &contributor) - amount;
+        save_contribution(&env, campaign_index, &contributor, remaining);
+        let released = release_match(&env, campaign_index, &contributor, &token, amount);
+        let asset_raised = load_asset_raised(&env, campaign_index, &token);
+        save_asset_raised(&env, campaign_index, &token, asset_raised - amount - released);
+        campaign.amount_raised -= amount + released;
+        save_campaign(&env, campaign_index, &campaign);
+        release_reward_tiers(&env, campaign_index, &contributor, remaining);
+        if remaining == 0 {
+            burn_receipt(&env, campaign_index, &contributor);
+        }
+
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &contributor, &amount);
+
+        env.events().publish(
+            (PLEDGE_WITHDRAWN, contributor),
+            (campaign_index, token, amount),
+        );
+
+        Ok(())
+    }
+
+    // Refund a contributor once a campaign has missed its funding threshold or been cancelled
+    pub fn refund(env: Env, contributor: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+
+        if campaign.status != CampaignStatus::Cancelled {
+            if env.ledger().timestamp() < campaign.deadline {
+                return Err(Error::DeadlineNotPassed);
+            }
+            if current_status(&env, &campaign) != CampaignStatus::Failed {
+                return Err(Error::TargetReached);
+            }
+        }
+
+        let amount = load_contribution(&env, campaign_index, &contributor);
+        if amount <= 0 {
+            return Err(Error::NothingToRefund);
+        }
+
+        // Entries are zeroed as each asset is paid so a second refund is rejected
+        for refunded in return_pledge(&env, campaign_index, &campaign, &contributor).iter() {
+            env.events().publish(
+                (REFUND_ISSUED, contributor.clone()),
+                (campaign_index, refunded.token, refunded.amount),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Pay the escrowed funds out to the creator of a successful campaign
+    pub fn claim(env: Env, creator: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        if env.ledger().timestamp() < campaign.deadline {
+            return Err(Error::DeadlineNotPassed);
+        }
+        if campaign.status == CampaignStatus::Claimed {
+            return Err(Error::AlreadyClaimed);
+        }
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+        if current_status(&env, &campaign) != CampaignStatus::Successful {
+            return Err(Error::TargetNotReached);
+        }
+        if load_tranches(&env, campaign_index).is_some() {
+            return Err(Error::TranchedPayout);
+        }
+
+        campaign.status = CampaignStatus::Claimed;
+        save_campaign(&env, campaign_index, &campaign);
+
+        // Each asset is paid out in the currency it was raised in
+        for token in campaign.accepted_tokens.iter() {
+            let asset_raised = load_asset_raised(&env, campaign_index, &token);
+            if asset_raised <= 0 {
+                continue;
+            }
+            let fee = pay_creator(&env, campaign_index, &token, &creator, asset_raised);
+
+            env.events().publish(
+                (FUNDS_CLAIMED, creator.clone()),
+                (campaign_index, token, asset_raised - fee, fee),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Creator adds addresses to, or removes them from, an allowlisted campaign
+    pub fn update_allowlist(
+        env: Env,
+        creator: Address,
+        campaign_index: u32,
+        addresses: Vec<Address>,
+        allowed: bool,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        let has_allowlist = load_rules(&env, campaign_index).is_some_and(|rules| rules.allowlist.is_some());
+        if !has_allowlist {
+            return Err(Error::InvalidRules);
+        }
+
+        set_allowlisted(&env, campaign_index, &addresses, allowed);
+
+        Ok(())
     }
 
+    // Sponsor deposits a budget that matches contributions to the listed campaigns at `ratio_bps`,
+    // up to `campaign_cap` per campaign. A campaign can be matched by only one pool.
+    pub fn create_match_pool(
+        env: Env,
+        sponsor: Address,
+        token: Address,
+        deposit: i128,
+        ratio_bps: u32,
+        campaign_cap: i128,
+        campaigns: Vec<u32>,
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        sponsor.require_auth();
+        if deposit <= 0 || ratio_bps == 0 || campaign_cap <= 0 {
+            return Err(Error::InvalidPool);
+        }
+        if campaigns.is_empty() || campaigns.len() > MAX_POOL_CAMPAIGNS {
+            return Err(Error::InvalidPool);
+        }
 
-    pub fn create_campaign(
+        let mut expires_at: u64 = 0;
+        for campaign_index in campaigns.iter() {
+            let campaign = load_campaign(&env, campaign_index)?;
+            if current_status(&env, &campaign) != CampaignStatus::Active {
+                return Err(Error::DeadlinePassed);
+            }
+            if !campaign.accepted_tokens.contains(&token) {
+                return Err(Error::TokenNotAccepted);
+            }
+            if env.storage().persistent().has(&DataKey::CampaignPool(campaign_index)) {
+                return Err(Error::CampaignAlreadyMatched);
+            }
+            expires_at = expires_at.max(campaign.deadline);
+        }
+
+        token::Client::new(&env, &token).transfer(&sponsor, &env.current_contract_address(), &deposit);
+
+        let pool_id: u32 = env.storage().persistent().get(&DataKey::PoolCount).unwrap_or(0);
+        for campaign_index in campaigns.iter() {
+            env.storage().persistent().set(&DataKey::CampaignPool(campaign_index), &pool_id);
+        }
+        save_pool(
+            &env,
+            pool_id,
+            &MatchPool {
+                sponsor: sponsor.clone(),
+                token: token.clone(),
+                ratio_bps,
+                campaign_cap,
+                campaigns,
+                remaining: deposit,
+                expires_at,
+            },
+        );
+        env.storage().persistent().set(&DataKey::PoolCount, &(pool_id + 1));
+
+        env.events().publish(
+            (POOL_CREATED, sponsor),
+            (pool_id, token, deposit, ratio_bps),
+        );
+
+        Ok(pool_id)
+    }
+
+    // Once every eligible campaign's deadline has passed, the sponsor takes back the undrawn budget
+    // along with anything matched into campaigns that failed, were cancelled or had their tranches stopped.
+    // Can be called again later as more campaigns settle.
+    pub fn reclaim_match_pool(env: Env, sponsor: Address, pool_id: u32) -> Result<i128, Error> {
+        check_initialized(&env)?;
+        sponsor.require_auth();
+
+        let mut pool = load_pool(&env, pool_id)?;
+        if sponsor != pool.sponsor {
+            return Err(Error::Unauthorized);
+        }
+        let now = env.ledger().timestamp();
+        if now < pool.expires_at {
+            return Err(Error::DeadlineNotPassed);
+        }
+        // The match stays on offer for as long as any eligible campaign is still open
+        for campaign_index in pool.campaigns.iter() {
+            let campaign = load_campaign(&env, campaign_index)?;
+            if campaign.status != CampaignStatus::Cancelled && now < campaign.deadline {
+                return Err(Error::DeadlineNotPassed);
+            }
+        }
+
+        let mut amount = pool.remaining;
+        for campaign_index in pool.campaigns.iter() {
+            let matched = load_pool_matched(&env, campaign_index);
+            if matched <= 0 {
+                continue;
+            }
+            let campaign = load_campaign(&env, campaign_index)?;
+            let returned = match current_status(&env, &campaign) {
+                CampaignStatus::Failed | CampaignStatus::Cancelled => matched,
+                _ => match load_tranches(&env, campaign_index) {
+                    // The sponsor shares in the unreleased funds like any contributor
+                    Some(schedule) if schedule.stopped => {
+                        let asset_raised = load_asset_raised(&env, campaign_index, &pool.token);
+                        let unreleased =
+                            asset_raised - released_through(&schedule, asset_raised, schedule.released_count);
+                        matched * unreleased / asset_raised
+                    }
+                    _ => continue,
+                },
+            };
+            env.storage().persistent().set(&DataKey::PoolMatched(campaign_index), &0i128);
+            amount += returned;
+        }
+        if amount <= 0 {
+            return Err(Error::NothingToRefund);
+        }
+
+        pool.remaining = 0;
+        save_pool(&env, pool_id, &pool);
+
+        token::Client::new(&env, &pool.token).transfer(&env.current_contract_address(), &sponsor, &amount);
+
+        env.events().publish(
+            (POOL_RECLAIMED, sponsor),
+            (pool_id, amount),
+        );
+
+        Ok(amount)
+    }
+
+    // Open a quadratic funding round over the listed campaigns. Anyone can add to its
+    // matching pool until it closes.
+    pub fn create_round(
         env: Env,
-        creator: Address,
-        title: String,
-        description: String,
-        target_amount: i64,
-        deadline: u64, 
-    ) {
-    
-        let current_timestamp = env.ledger().timestamp();
-        assert!(deadline > current_timestamp, "Deadline must be in the future");
+        operator: Address,
+        token: Address,
+        campaigns: Vec<u32>,
+        closes_at: u64,
+        verifier: Option<Address>,
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        operator.require_auth();
+        if campaigns.is_empty() || campaigns.len() > MAX_ROUND_CAMPAIGNS {
+            return Err(Error::InvalidRound);
+        }
+        if closes_at <= env.ledger().timestamp() {
+            return Err(Error::InvalidDeadline);
+        }
+        for (position, campaign_index) in campaigns.iter().enumerate() {
+            if campaigns.first_index_of(campaign_index) != Some(position as u32) {
+                return Err(Error::InvalidRound);
+            }
+            let campaign = load_campaign(&env, campaign_index)?;
+            if !campaign.accepted_tokens.contains(&token) {
+                return Err(Error::TokenNotAccepted);
+            }
+            // Contributions can't be withdrawn once the campaigns are over, so scores are final at close
+            if closes_at < campaign.deadline {
+                return Err(Error::InvalidDeadline);
+            }
+        }
 
-        
-        let campaign = vec![
+        let round_id: u32 = env.storage().persistent().get(&DataKey::RoundCount).unwrap_or(0);
+        save_round(
             &env,
-            creator.clone(),
-            title,
-            description,
-            target_amount.into(),
-            deadline.into(),
-            0i64.into(), 
-        ];
-
-        
-        let mut campaigns: Vec<Vec<soroban_sdk::Val>> = env
+            round_id,
+            &Round {
+                operator: operator.clone(),
+                token,
+                campaigns,
+                closes_at,
+                verifier,
+                pool: 0,
+                closed: false,
+            },
+        );
+        env.storage().persistent().set(&DataKey::RoundCount, &(round_id + 1));
+
+        env.events().publish(
+            (ROUND_CREATED, operator),
+            (round_id, closes_at),
+        );
+
+        Ok(round_id)
+    }
+
+    // Deposit into a round's matching pool
+    pub fn fund_round(env: Env, funder: Address, round_id: u32, amount: i128) -> Result<(), Error> {
+        check_initialized(&env)?;
+        funder.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let mut round = load_round(&env, round_id)?;
+        if round.closed {
+            return Err(Error::RoundClosed);
+        }
+        if env.ledger().timestamp() >= round.closes_at {
+            return Err(Error::DeadlinePassed);
+        }
+
+        token::Client::new(&env, &round.token).transfer(&funder, &env.current_contract_address(), &amount);
+        round.pool += amount;
+        save_round(&env, round_id, &round);
+
+        env.events().publish(
+            (ROUND_FUNDED, funder),
+            (round_id, amount),
+        );
+
+        Ok(())
+    }
+
+    // Count up to `limit` contributors of a verified round, asking the verifier about each. Anyone
+    // can call this once the round could close, repeating until it returns true; a round without
+    // a verifier needs no tally.
+    pub fn tally_round(env: Env, round_id: u32, limit: u32) -> Result<bool, Error> {
+        check_initialized(&env)?;
+
+        let round = load_round(&env, round_id)?;
+        check_round_over(&env, &round)?;
+        let verifier = match &round.verifier {
+            Some(verifier) => KycVerifierClient::new(&env, verifier),
+            None => return Ok(true),
+        };
+
+        let mut tally = load_round_tally(&env, round_id);
+        let mut remaining = limit.min(MAX_PAGE_SIZE);
+        while tally.campaign_position < round.campaigns.len() {
+            let position = tally.campaign_position;
+            if tally.root_sums.len() == position {
+                tally.root_sums.push_back(0);
+            }
+            let campaign_index = round.campaigns.get(position).unwrap();
+            let campaign = load_campaign(&env, campaign_index)?;
+            let scored = scores_in_round(&env, &campaign);
+            if !scored || tally.contributor_position >= contributor_count(&env, campaign_index) {
+                tally.campaign_position += 1;
+                tally.contributor_position = 0;
+                continue;
+            }
+            if remaining == 0 {
+                break;
+            }
+
+            let contributor = load_contributor(&env, campaign_index, tally.contributor_position);
+            let amount = load_asset_contribution(&env, campaign_index, &contributor, &round.token);
+            if amount > 0 && verifier.is_verified(&contributor) {
+                tally.root_sums.set(position, tally.root_sums.get(position).unwrap() + isqrt(amount));
+            }
+            tally.contributor_position += 1;
+            remaining -= 1;
+        }
+        env.storage().persistent().set(&DataKey::RoundTally(round_id), &tally);
+
+        Ok(tally.campaign_position == round.campaigns.len())
+    }
+
+    // Close a round once its time is up and every campaign has ended, computing each successful
+    // campaign's share of the matching pool and paying it to the campaign's creator. Anyone can
+    // close; a verified round must be fully tallied first. Whatever is not allocated, including
+    // rounding dust, goes back to the operator.
+    pub fn close_round(env: Env, round_id: u32) -> Result<Vec<RoundAllocation>, Error> {
+        check_initialized(&env)?;
+
+        let mut round = load_round(&env, round_id)?;
+        check_round_over(&env, &round)?;
+        if round.verifier.is_some() && load_round_tally(&env, round_id).campaign_position < round.campaigns.len() {
+            return Err(Error::TallyIncomplete);
+        }
+
+        let mut scores = Vec::new(&env);
+        let mut total_score: i128 = 0;
+        for (position, campaign_index) in round.campaigns.iter().enumerate() {
+            let score = round_score(&env, round_id, &round, position as u32, campaign_index)?;
+            total_score += score;
+            scores.push_back(score);
+        }
+
+        // Shares are rounded down
+        let mut allocations = Vec::new(&env);
+        let mut allocated: i128 = 0;
+        for (position, campaign_index) in round.campaigns.iter().enumerate() {
+            let score = scores.get(position as u32).unwrap();
+            let amount = if score > 0 { mul_div(round.pool, score, total_score) } else { 0 };
+            allocated += amount;
+            allocations.push_back(RoundAllocation { campaign_index, score, amount });
+        }
+
+        round.closed = true;
+        save_round(&env, round_id, &round);
+        env.storage().persistent().set(&DataKey::RoundAllocations(round_id), &allocations);
+
+        let client = token::Client::new(&env, &round.token);
+        let unallocated = round.pool - allocated;
+        if unallocated > 0 {
+            client.transfer(&env.current_contract_address(), &round.operator, &unallocated);
+        }
+        for allocation in allocations.iter() {
+            if allocation.amount <= 0 {
+                continue;
+            }
+            let creator = load_campaign(&env, allocation.campaign_index)?.creator;
+            client.transfer(&env.current_contract_address(), &creator, &allocation.amount);
+
+            env.events().publish(
+                (QF_ALLOCATED, allocation.campaign_index),
+                (round_id, creator, allocation.amount),
+            );
+        }
+
+        env.events().publish(
+            (ROUND_CLOSED, round_id),
+            (round.pool, total_score, unallocated),
+        );
+
+        Ok(allocations)
+    }
+
+    // Pledge `amount` every `period` seconds until the deadline. The subscriber approves this contract
+    // to spend the installments; the first one is due straight away.
+    pub fn subscribe(
+        env: Env,
+        subscriber: Address,
+        campaign_index: u32,
+        token: Address,
+        amount: i128,
+        period: u64,
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        subscriber.require_auth();
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if period == 0 {
+            return Err(Error::InvalidPeriod);
+        }
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+        if env.ledger().timestamp() >= campaign.deadline {
+            return Err(Error::DeadlinePassed);
+        }
+        if !campaign.accepted_tokens.contains(&token) {
+            return Err(Error::TokenNotAccepted);
+        }
+
+        let subscription_id: u32 = env.storage().persistent().get(&DataKey::SubscriptionCount).unwrap_or(0);
+        let subscription = Subscription {
+            subscriber: subscriber.clone(),
+            campaign_index,
+            token: token.clone(),
+            amount,
+            period,
+            next_payment: env.ledger().timestamp(),
+            installments_paid: 0,
+            missed_payments: 0,
+            active: true,
+        };
+        save_subscription(&env, subscription_id, &subscription);
+        env.storage().persistent().set(&DataKey::SubscriptionCount, &(subscription_id + 1));
+
+        env.events().publish(
+            (SUBSCRIBED, subscriber),
+            (subscription_id, campaign_index, token, amount, period),
+        );
+
+        Ok(subscription_id)
+    }
+
+    // Stop a subscription. Installments already paid stay pledged to the campaign.
+    pub fn cancel_subscription(env: Env, subscriber: Address, subscription_id: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        subscriber.require_auth();
+
+        let mut subscription = load_subscription(&env, subscription_id)?;
+        if subscriber != subscription.subscriber {
+            return Err(Error::Unauthorized);
+        }
+        if !subscription.active {
+            return Err(Error::SubscriptionInactive);
+        }
+        subscription.active = false;
+        save_subscription(&env, subscription_id, &subscription);
+
+        env.events().publish(
+            (SUBSCRIPTION_CANCELLED, subscriber),
+            (subscription_id, subscription.campaign_index),
+        );
+
+        Ok(())
+    }
+
+    // Collect due installments for a page of subscriptions. Anyone may call this; each call takes at
+    // most one installment per subscription. Returns how many installments were collected.
+    pub fn process_subscriptions(env: Env, start: u32, limit: u32) -> Result<u32, Error> {
+        check_initialized(&env)?;
+
+        let now = env.ledger().timestamp();
+        let count: u32 = env.storage().persistent().get(&DataKey::SubscriptionCount).unwrap_or(0);
+        let end = count.min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut collected = 0;
+        for subscription_id in start..end {
+            let mut subscription = load_subscription(&env, subscription_id)?;
+            if !subscription.active || now < subscription.next_payment {
+                continue;
+            }
+            let campaign = load_campaign(&env, subscription.campaign_index)?;
+
+            // Subscriptions end with the campaign's funding period
+            if campaign.status == CampaignStatus::Cancelled || now >= campaign.deadline {
+                subscription.active = false;
+                save_subscription(&env, subscription_id, &subscription);
+                env.events().publish(
+                    (SUBSCRIPTION_ENDED, subscription.subscriber),
+                    (subscription_id, subscription.campaign_index),
+                );
+                continue;
+            }
+
+            subscription.next_payment += subscription.period;
+            if collect_installment(&env, &subscription, campaign) {
+                subscription.installments_paid += 1;
+                subscription.missed_payments = 0;
+                collected += 1;
+                env.events().publish(
+                    (INSTALLMENT_PAID, subscription.subscriber.clone()),
+                    (subscription_id, subscription.campaign_index, subscription.amount),
+                );
+            } else {
+                subscription.missed_payments += 1;
+                env.events().publish(
+                    (INSTALLMENT_MISSED, subscription.subscriber.clone()),
+                    (subscription_id, subscription.campaign_index, subscription.missed_payments),
+                );
+                if subscription.missed_payments >= MAX_MISSED_PAYMENTS {
+                    subscription.active = false;
+                    env.events().publish(
+                        (SUBSCRIPTION_CANCELLED, subscription.subscriber.clone()),
+                        (subscription_id, subscription.campaign_index),
+                    );
+                }
+            }
+            save_subscription(&env, subscription_id, &subscription);
+        }
+
+        Ok(collected)
+    }
+
+    // Re-rank a page of a campaign's contributors on the leaderboard. Anyone may call this, e.g.
+    // after a listed backer withdrew; a pass over every contributor leaves the leaderboard exact.
+    pub fn refresh_top_backers(env: Env, campaign_index: u32, start: u32, limit: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        load_campaign(&env, campaign_index)?;
+
+        let end = contributor_count(&env, campaign_index).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        for position in start..end {
+            let contributor = load_contributor(&env, campaign_index, position);
+            let amount = load_contribution(&env, campaign_index, &contributor);
+            update_top_backers(&env, campaign_index, &contributor, amount);
+        }
+
+        Ok(())
+    }
+
+    // Hand a receipt, and with it the backing position and its refund claim, to another address.
+    // The receiver must never have backed the campaign itself, and must pass the campaign's
+    // allowlist, KYC and per-contributor limit like any contributor.
+    pub fn transfer_receipt(env: Env, from: Address, to: Address, receipt_id: u64) -> Result<(), Error> {
+        check_initialized(&env)?;
+        from.require_auth();
+
+        let mut receipt = load_receipt(&env, receipt_id)?;
+        if from != receipt.owner {
+            return Err(Error::Unauthorized);
+        }
+        let campaign_index = receipt.campaign_index;
+        if env.storage().persistent().has(&DataKey::Contribution(campaign_index, to.clone())) {
+            return Err(Error::ReceiverHasPosition);
+        }
+        let campaign = load_campaign(&env, campaign_index)?;
+        if let Some(rules) = load_rules(&env, campaign_index) {
+            check_holder(&env, campaign_index, &rules, &to, load_contribution(&env, campaign_index, &from))?;
+        }
+
+        move_position(&env, campaign_index, &campaign, &from, &to);
+        env.storage().persistent().remove(&DataKey::ReceiptOf(campaign_index, from.clone()));
+        env.storage().persistent().set(&DataKey::ReceiptOf(campaign_index, to.clone()), &receipt_id);
+        receipt.owner = to.clone();
+        env.storage().persistent().set(&DataKey::Receipt(receipt_id), &receipt);
+
+        env.events().publish(
+            (RECEIPT_TRANSFERRED, from),
+            (receipt_id, to),
+        );
+
+        Ok(())
+    }
+
+    // Admin sets the platform fee charged on creator payouts and the treasury that receives it
+    pub fn set_fee(env: Env, admin: Address, fee_bps: u32, treasury: Address) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+        if admin != load_admin(&env)? {
+            return Err(Error::Unauthorized);
+        }
+        if fee_bps > MAX_FEE_BPS {
+            return Err(Error::InvalidFee);
+        }
+
+        env.storage().persistent().set(&DataKey::FeeConfig, &FeeConfig { fee_bps, treasury });
+
+        Ok(())
+    }
+
+    // Admin overrides the fee for a single campaign, or clears the override with `None`
+    pub fn set_campaign_fee(
+        env: Env,
+        admin: Address,
+        campaign_index: u32,
+        fee_bps: Option<u32>,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+        if admin != load_admin(&env)? {
+            return Err(Error::Unauthorized);
+        }
+        load_campaign(&env, campaign_index)?;
+
+        match fee_bps {
+            Some(fee_bps) if fee_bps > MAX_FEE_BPS => return Err(Error::InvalidFee),
+            Some(fee_bps) => env.storage().persistent().set(&DataKey::FeeOverride(campaign_index), &fee_bps),
+            None => env.storage().persistent().remove(&DataKey::FeeOverride(campaign_index)),
+        }
+
+        Ok(())
+    }
+
+    // Admin sets how far past its original deadline any campaign may be extended
+    pub fn set_max_extension(env: Env, admin: Address, max_extension: u64) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+        if admin != load_admin(&env)? {
+            return Err(Error::Unauthorized);
+        }
+
+        env.storage().persistent().set(&DataKey::MaxExtension, &max_extension);
+
+        Ok(())
+    }
+
+    // Admin declares which unit an asset is denominated in, e.g. "USD". Only assets sharing a unit
+    // can be accepted together by one campaign. Existing campaigns are unaffected by changes.
+    pub fn set_asset_unit(env: Env, admin: Address, token: Address, unit: Option<Symbol>) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+        if admin != load_admin(&env)? {
+            return Err(Error::Unauthorized);
+        }
+
+        let key = DataKey::AssetUnit(token);
+        match unit {
+            Some(unit) => env.storage().persistent().set(&key, &unit),
+            None => env.storage().persistent().remove(&key),
+        }
+
+        Ok(())
+    }
+
+    // Change the target or extend the deadline of a running campaign. Contributors who
+    // pledged under the old terms get an opt-out window to withdraw in full.
+    pub fn update_campaign(
+        env: Env,
+        creator: Address,
+        campaign_index: u32,
+        target_amount: i128,
+        deadline: u64,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        match current_status(&env, &campaign) {
+            CampaignStatus::Active => {}
+            CampaignStatus::Cancelled => return Err(Error::CampaignCancelled),
+            _ => return Err(Error::DeadlinePassed),
+        }
+        if target_amount == campaign.target_amount && deadline == campaign.deadline {
+            return Ok(());
+        }
+
+        if target_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if target_amount < campaign.amount_raised {
+            return Err(Error::TargetBelowRaised);
+        }
+        let hard_cap = load_rules(&env, campaign_index).and_then(|rules| rules.hard_cap);
+        if hard_cap.is_some_and(|cap| target_amount > cap) {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Deadlines only move out, and far enough that the opt-out window closes before settlement
+        let opt_out_until = env.ledger().timestamp() + OPT_OUT_WINDOW;
+        if deadline < campaign.deadline || deadline < opt_out_until {
+            return Err(Error::InvalidDeadline);
+        }
+        let max_extension: u64 = env
             .storage()
             .persistent()
-            .get(&symbol_short!("campaigns"))
-            .unwrap_or_else(|| Vec::new(&env));
-        campaigns.push_back(campaign);
+            .get(&DataKey::MaxExtension)
+            .unwrap_or(DEFAULT_MAX_EXTENSION);
+        if deadline - campaign.original_deadline > max_extension {
+            return Err(Error::ExtensionTooLong);
+        }
 
-        
-        env.storage().persistent().set(&symbol_short!("campaigns"), &campaigns);
+        campaign.target_amount = target_amount;
+        campaign.deadline = deadline;
+        campaign.terms_version += 1;
+        campaign.opt_out_until = opt_out_until;
+        save_campaign(&env, campaign_index, &campaign);
 
         env.events().publish(
-            (CAMPAIGN_CREATED, creator),
-            (title, target_amount, deadline),
+            (CAMPAIGN_UPDATED, creator),
+            (campaign_index, target_amount, deadline, opt_out_until),
+        );
+
+        Ok(())
+    }
+
+    // Replace the title and content hash. Every version is kept in the metadata history.
+    pub fn update_metadata(
+        env: Env,
+        creator: Address,
+        campaign_index: u32,
+        title: String,
+        content_hash: BytesN<32>,
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        if title.len() > MAX_TITLE_LENGTH {
+            return Err(Error::InvalidCampaign);
+        }
+
+        campaign.title = title;
+        campaign.content_hash = content_hash.clone();
+        campaign.metadata_version += 1;
+        save_campaign(&env, campaign_index, &campaign);
+        push_metadata_version(&env, campaign_index, &campaign);
+
+        env.events().publish(
+            (METADATA_UPDATED, creator),
+            (campaign_index, campaign.metadata_version, content_hash),
         );
+
+        Ok(campaign.metadata_version)
     }
 
-    pub fn contribute(env: Env, contributor: Address, campaign_index: u32, amount: i64) {
-        assert!(amount > 0, "Contribution amount must be positive");
+    // Withdraw a whole contribution after the campaign's terms changed, while the opt-out window is open
+    pub fn opt_out(env: Env, contributor: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+        if env.ledger().timestamp() >= campaign.opt_out_until {
+            return Err(Error::OptOutClosed);
+        }
 
-        
-        let mut campaigns: Vec<Vec<soroban_sdk::Val>> = env
+        let amount = load_contribution(&env, campaign_index, &contributor);
+        if amount <= 0 {
+            return Err(Error::NothingToRefund);
+        }
+        let accepted_terms: u32 = env
             .storage()
             .persistent()
-            .get(&symbol_short!("campaigns"))
-            .unwrap_or_else(|| Vec::new(&env));
+            .get(&DataKey::PledgeTerms(campaign_index, contributor.clone()))
+            .unwrap_or(0);
+        if accepted_terms >= campaign.terms_version {
+            return Err(Error::TermsAccepted);
+        }
 
-        assert!(
-            campaign_index < campaigns.len(),
-            "Campaign does not exist"
+        for returned in return_pledge(&env, campaign_index, &campaign, &contributor).iter() {
+            let released = release_match(&env, campaign_index, &contributor, &returned.token, returned.amount);
+            campaign.amount_raised -= released;
+            let asset_raised = load_asset_raised(&env, campaign_index, &returned.token);
+            save_asset_raised(&env, campaign_index, &returned.token, asset_raised - returned.amount - released);
+
+            env.events().publish(
+                (OPTED_OUT, contributor.clone()),
+                (campaign_index, returned.token, returned.amount),
+            );
+        }
+        campaign.amount_raised -= amount;
+        save_campaign(&env, campaign_index, &campaign);
+        release_reward_tiers(&env, campaign_index, &contributor, 0);
+
+        Ok(())
+    }
+
+    // Stop a campaign that has not succeeded. The creator or the contract admin can cancel,
+    // after which every contributor can refund their full contribution.
+    pub fn cancel_campaign(env: Env, caller: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        caller.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        if caller != campaign.creator && caller != load_admin(&env)? {
+            return Err(Error::Unauthorized);
+        }
+        // A campaign that succeeded at its deadline is owed to the creator, and a tranche schedule
+        // can only be halted by its backers' vote
+        match current_status(&env, &campaign) {
+            CampaignStatus::Claimed => return Err(Error::AlreadyClaimed),
+            CampaignStatus::Cancelled => return Err(Error::CampaignCancelled),
+            CampaignStatus::Successful => return Err(Error::TargetReached),
+            _ => {}
+        }
+
+        campaign.status = CampaignStatus::Cancelled;
+        save_campaign(&env, campaign_index, &campaign);
+
+        env.events().publish(
+            (CAMPAIGN_CANCELLED, caller),
+            (campaign_index, campaign.amount_raised),
         );
 
-        let mut campaign = campaigns.get(campaign_index).unwrap();
+        Ok(())
+    }
 
-        
-        let deadline: u64 = campaign.get(5).unwrap().unwrap().try_into().unwrap();
-        let current_timestamp = env.ledger().timestamp();
-        assert!(
-            current_timestamp < deadline,
-            "Campaign deadline has passed"
+    // Split the creator's payout into tranches. Only allowed before anyone has contributed.
+    pub fn set_tranches(
+        env: Env,
+        creator: Address,
+        campaign_index: u32,
+        tranche_bps: Vec<u32>,
+        arbiter: Option<Address>,
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        if campaign.status == CampaignStatus::Cancelled {
+            return Err(Error::CampaignCancelled);
+        }
+        if current_status(&env, &campaign) != CampaignStatus::Active {
+            return Err(Error::DeadlinePassed);
+        }
+        if campaign.amount_raised > 0 {
+            return Err(Error::ContributionsStarted);
+        }
+        validate_tranches(&tranche_bps)?;
+
+        save_tranches(
+            &env,
+            campaign_index,
+            &TrancheSchedule {
+                tranche_bps,
+                arbiter,
+                released_count: 0,
+                released_amount: 0,
+                requested: false,
+                approval_round: 0,
+                approval_weight: 0,
+                stop_weight: 0,
+                stopped: false,
+            },
         );
 
+        Ok(())
+    }
+
+    // Creator asks for the next tranche to be unlocked
+    pub fn request_tranche(env: Env, creator: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
 
-        let mut amount_raised: i64 = campaign.get(6).unwrap().unwrap().try_into().unwrap();
-        amount_raised += amount;
-        campaign.set(6, amount_raised.into());
+        let campaign = load_campaign(&env, campaign_index)?;
+        if creator != campaign.creator {
+            return Err(Error::Unauthorized);
+        }
+        let mut schedule = load_running_tranches(&env, &campaign, campaign_index)?;
+        if schedule.requested {
+            return Err(Error::InvalidTrancheRequest);
+        }
 
-        
-        campaigns.set(campaign_index, campaign);
-        env.storage().persistent().set(&symbol_short!("campaigns"), &campaigns);
+        schedule.requested = true;
+        schedule.approval_round += 1;
+        schedule.approval_weight = 0;
+        let tranche_index = schedule.released_count;
+        save_tranches(&env, campaign_index, &schedule);
 
+        env.events().publish(
+            (TRANCHE_REQUESTED, creator),
+            (campaign_index, tranche_index),
+        );
 
-        let mut contributions: Map<Address, Map<u32, i64>> = env
-            .storage()
-            .persistent()
-            .get(&symbol_short!("contributions"))
-            .unwrap_or_else(|| Map::new(&env));
+        Ok(())
+    }
+
+    // Contributors approve the requested tranche with the weight of their contribution
+    pub fn approve_tranche(env: Env, contributor: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        let mut schedule = load_running_tranches(&env, &campaign, campaign_index)?;
+        if !schedule.requested {
+            return Err(Error::InvalidTrancheRequest);
+        }
 
-        let mut user_contributions = contributions.get(contributor.clone()).unwrap_or_else(|| Map::new(&env));
-        let current_contribution: i64 = user_contributions.get(campaign_index).unwrap_or(0);
-        user_contributions.set(campaign_index, current_contribution + amount);
-        contributions.set(contributor.clone(), user_contributions);
-        env.storage().persistent().set(&symbol_short!("contributions"), &contributions);
+        let weight = load_contribution(&env, campaign_index, &contributor);
+        if weight <= 0 {
+            return Err(Error::NotContributor);
+        }
+        let approval_key = DataKey::TrancheApproval(campaign_index, contributor.clone());
+        let last_round: u32 = env.storage().persistent().get(&approval_key).unwrap_or(0);
+        if last_round == schedule.approval_round {
+            return Err(Error::AlreadyVoted);
+        }
+        env.storage().persistent().set(&approval_key, &schedule.approval_round);
+
+        schedule.approval_weight += weight;
+        save_tranches(&env, campaign_index, &schedule);
 
-        // Emit event
         env.events().publish(
-            (CONTRIBUTION_MADE, contributor),
-            (campaign_index, amount),
+            (TRANCHE_APPROVED, contributor),
+            (campaign_index, schedule.released_count, weight),
         );
+
+        Ok(())
+    }
+
+    // Pay out the requested tranche once the arbiter signs off or a majority of contributions approved it
+    pub fn release_tranche(env: Env, caller: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        caller.require_auth();
+
+        let mut campaign = load_campaign(&env, campaign_index)?;
+        let mut schedule = load_running_tranches(&env, &campaign, campaign_index)?;
+        if !schedule.requested {
+            return Err(Error::InvalidTrancheRequest);
+        }
+
+        let by_arbiter = schedule.arbiter == Some(caller.clone());
+        if !by_arbiter && schedule.approval_weight * 2 <= backer_weight(&env, campaign_index, &campaign) {
+            return Err(Error::TrancheNotApproved);
+        }
+
+        let tranche_index = schedule.released_count;
+        let mut payouts = Vec::new(&env);
+        for token in campaign.accepted_tokens.iter() {
+            let asset_raised = load_asset_raised(&env, campaign_index, &token);
+            let amount = released_through(&schedule, asset_raised, tranche_index + 1)
+                - released_through(&schedule, asset_raised, tranche_index);
+            if amount > 0 {
+                schedule.released_amount += amount;
+                payouts.push_back(AssetAmount { token, amount });
+            }
+        }
+
+        schedule.released_count += 1;
+        schedule.requested = false;
+        schedule.approval_weight = 0;
+        save_tranches(&env, campaign_index, &schedule);
+
+        if schedule.released_count == schedule.tranche_bps.len() {
+            campaign.status = CampaignStatus::Claimed;
+            save_campaign(&env, campaign_index, &campaign);
+        }
+
+        for payout in payouts.iter() {
+            let fee = pay_creator(&env, campaign_index, &payout.token, &campaign.creator, payout.amount);
+
+            env.events().publish(
+                (TRANCHE_RELEASED, campaign.creator.clone()),
+                (campaign_index, tranche_index, payout.token, payout.amount - fee, fee),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Contributors vote to halt the remaining tranches. A majority of backers' contributions stops them.
+    pub fn vote_stop_tranches(env: Env, contributor: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        let mut schedule = load_running_tranches(&env, &campaign, campaign_index)?;
+
+        let weight = load_contribution(&env, campaign_index, &contributor);
+        if weight <= 0 {
+            return Err(Error::NotContributor);
+        }
+        let stop_key = DataKey::StopVote(campaign_index, contributor.clone());
+        if env.storage().persistent().has(&stop_key) {
+            return Err(Error::AlreadyVoted);
+        }
+        env.storage().persistent().set(&stop_key, &true);
+
+        schedule.stop_weight += weight;
+        if schedule.stop_weight * 2 > backer_weight(&env, campaign_index, &campaign) {
+            schedule.stopped = true;
+            schedule.requested = false;
+            env.events().publish(
+                (TRANCHES_STOPPED, contributor),
+                (campaign_index, campaign.amount_raised - schedule.released_amount),
+            );
+        }
+        save_tranches(&env, campaign_index, &schedule);
+
+        Ok(())
+    }
+
+    // After the tranches were stopped, contributors reclaim their share of the unreleased funds
+    pub fn reclaim(env: Env, contributor: Address, campaign_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        contributor.require_auth();
+
+        let campaign = load_campaign(&env, campaign_index)?;
+        let schedule = load_tranches(&env, campaign_index).ok_or(Error::NotFound)?;
+        if !schedule.stopped {
+            return Err(Error::TranchesNotStopped);
+        }
+
+        let contribution = load_contribution(&env, campaign_index, &contributor);
+        if contribution <= 0 {
+            return Err(Error::NothingToRefund);
+        }
+        save_contribution(&env, campaign_index, &contributor, 0);
+        burn_receipt(&env, campaign_index, &contributor);
+
+        // Each asset is returned in proportion to the contributor's share of what was raised in it
+        for token in campaign.accepted_tokens.iter() {
+            let asset_contribution = load_asset_contribution(&env, campaign_index, &contributor, &token);
+            if asset_contribution <= 0 {
+                continue;
+            }
+            save_asset_contribution(&env, campaign_index, &contributor, &token, 0);
+
+            let asset_raised = load_asset_raised(&env, campaign_index, &token);
+            let unreleased = asset_raised - released_through(&schedule, asset_raised, schedule.released_count);
+            let amount = asset_contribution * unreleased / asset_raised;
+            token::Client::new(&env, &token).transfer(&env.current_contract_address(), &contributor, &amount);
+
+            env.events().publish(
+                (FUNDS_RECLAIMED, contributor.clone()),
+                (campaign_index, token, amount),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn get_tranches(env: Env, campaign_index: u32) -> Result<TrancheSchedule, Error> {
+        load_tranches(&env, campaign_index).ok_or(Error::NotFound)
     }
 
     // Get campaign details
-    pub fn get_campaign(env: Env, campaign_index: u32) -> Vec<soroban_sdk::Val> {
-        let campaigns: Vec<Vec<soroban_sdk::Val>> = env
-            .storage()
+    pub fn get_campaign(env: Env, campaign_index: u32) -> Result<Campaign, Error> {
+        Ok(campaign_entry(&env, campaign_index)?.campaign)
+    }
+
+    // Page through every title and content hash the campaign has had, oldest first. Versions
+    // run from 0 to the campaign's current `metadata_version`.
+    pub fn get_metadata_history(
+        env: Env,
+        campaign_index: u32,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<MetadataVersion>, Error> {
+        let campaign = load_campaign(&env, campaign_index)?;
+
+        let end = (campaign.metadata_version + 1).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut history = Vec::new(&env);
+        for version in start..end {
+            history.push_back(
+                env.storage()
+                    .persistent()
+                    .get(&DataKey::MetadataVersion(campaign_index, version))
+                    .unwrap(),
+            );
+        }
+        Ok(history)
+    }
+
+    // Number of campaigns created so far. Campaign indexes run from 0 to count - 1.
+    pub fn campaign_count(env: Env) -> u32 {
+        campaign_count(&env)
+    }
+
+    // Page through all campaigns in creation order. At most 50 are returned per call.
+    pub fn list_campaigns(env: Env, start: u32, limit: u32) -> Result<Vec<CampaignEntry>, Error> {
+        Ok(scan_campaigns(&env, start, limit, |_| true)?.entries)
+    }
+
+    // Page through the campaigns started by one creator
+    pub fn list_campaigns_by_creator(
+        env: Env,
+        creator: Address,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<CampaignEntry>, Error> {
+        let end = creator_campaign_count(&env, &creator).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut entries = Vec::new(&env);
+        for position in start..end {
+            let campaign_index: u32 = env
+                .storage()
+                .persistent()
+                .get(&DataKey::CreatorCampaign(creator.clone(), position))
+                .unwrap();
+            entries.push_back(campaign_entry(&env, campaign_index)?);
+        }
+        Ok(entries)
+    }
+
+    // Campaigns still open for contributions, scanning forward from index `start`.
+    // Continue from the page's `next_start` to fetch the next page.
+    pub fn list_active_campaigns(env: Env, start: u32, limit: u32) -> Result<CampaignPage, Error> {
+        scan_campaigns(&env, start, limit, |campaign| campaign.status == CampaignStatus::Active)
+    }
+
+    // Campaigns whose deadline passed but that have not been claimed or cancelled,
+    // i.e. awaiting the creator's payout or contributors' refunds
+    pub fn list_awaiting_settlement(env: Env, start: u32, limit: u32) -> Result<CampaignPage, Error> {
+        scan_campaigns(&env, start, limit, |campaign| {
+            matches!(campaign.status, CampaignStatus::Successful | CampaignStatus::Failed)
+        })
+    }
+
+    // Get the milestones of a campaign along with whether each has been reached
+    pub fn get_milestones(env: Env, campaign_index: u32) -> Result<Vec<MilestoneState>, Error> {
+        let campaign = load_campaign(&env, campaign_index)?;
+
+        let mut states = Vec::new(&env);
+        for milestone in load_milestones(&env, campaign_index).iter() {
+            states.push_back(MilestoneState {
+                reached: campaign.amount_raised >= milestone.amount,
+                amount: milestone.amount,
+                label: milestone.label,
+            });
+        }
+        Ok(states)
+    }
+
+    // Get the reward tiers of a campaign with the supply left in each
+    pub fn get_reward_tiers(env: Env, campaign_index: u32) -> Result<Vec<RewardTierState>, Error> {
+        load_campaign(&env, campaign_index)?;
+
+        let mut states = Vec::new(&env);
+        for (tier_index, tier) in load_reward_tiers(&env, campaign_index).iter().enumerate() {
+            let claimed = load_tier_backers(&env, campaign_index, tier_index as u32).len();
+            states.push_back(RewardTierState {
+                min_amount: tier.min_amount,
+                quantity: tier.quantity,
+                remaining: tier.quantity - claimed,
+                description: tier.description,
+            });
+        }
+        Ok(states)
+    }
+
+    // Get the backers owed a reward tier's perk, in the order they selected it
+    pub fn get_backers_for_tier(env: Env, campaign_index: u32, tier_index: u32) -> Result<Vec<Address>, Error> {
+        load_campaign(&env, campaign_index)?;
+        if tier_index >= load_reward_tiers(&env, campaign_index).len() {
+            return Err(Error::InvalidTier);
+        }
+        Ok(load_tier_backers(&env, campaign_index, tier_index))
+    }
+
+    pub fn get_match_pool(env: Env, pool_id: u32) -> Result<MatchPool, Error> {
+        load_pool(&env, pool_id)
+    }
+
+    // Get how much sponsor money has been matched into a campaign
+    pub fn get_matched_amount(env: Env, campaign_index: u32) -> Result<i128, Error> {
+        load_campaign(&env, campaign_index)?;
+        Ok(load_pool_matched(&env, campaign_index))
+    }
+
+    pub fn get_round(env: Env, round_id: u32) -> Result<Round, Error> {
+        load_round(&env, round_id)
+    }
+
+    // Get the allocations a round paid out, available once it has closed
+    pub fn get_round_allocations(env: Env, round_id: u32) -> Result<Vec<RoundAllocation>, Error> {
+        load_round(&env, round_id)?;
+        env.storage()
             .persistent()
-            .get(&symbol_short!("campaigns"))
-            .unwrap_or_else(|| Vec::new(&env));
+            .get(&DataKey::RoundAllocations(round_id))
+            .ok_or(Error::NotFound)
+    }
 
-        assert!(
-            campaign_index < campaigns.len(),
-            "Campaign does not exist"
-        );
+    pub fn get_subscription(env: Env, subscription_id: u32) -> Result<Subscription, Error> {
+        load_subscription(&env, subscription_id)
+    }
 
-        campaigns.get(campaign_index).unwrap()
+    // Get the current holder of a receipt
+    pub fn receipt_owner(env: Env, receipt_id: u64) -> Result<Address, Error> {
+        Ok(load_receipt(&env, receipt_id)?.owner)
     }
 
-    // Get user contributions to a campaign
-    pub fn get_user_contributions(env: Env, user: Address, campaign_index: u32) -> i64 {
-        let contributions: Map<Address, Map<u32, i64>> = env
-            .storage()
+    // Get the receipt an address holds for a campaign, if any
+    pub fn get_receipt_id(env: Env, campaign_index: u32, owner: Address) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::ReceiptOf(campaign_index, owner))
+    }
+
+    // Describe a receipt for wallets: the campaign it backs and the position's current size
+    pub fn receipt_metadata(env: Env, receipt_id: u64) -> Result<ReceiptMetadata, Error> {
+        let receipt = load_receipt(&env, receipt_id)?;
+        let campaign = load_campaign(&env, receipt.campaign_index)?;
+        Ok(ReceiptMetadata {
+            id: receipt.id,
+            campaign_index: receipt.campaign_index,
+            campaign_title: campaign.title,
+            content_hash: campaign.content_hash,
+            amount: load_contribution(&env, receipt.campaign_index, &receipt.owner),
+            owner: receipt.owner,
+        })
+    }
+
+    // Get the platform fee configuration
+    pub fn get_fee_config(env: Env) -> Result<FeeConfig, Error> {
+        load_fee_config(&env).ok_or(Error::NotFound)
+    }
+
+    // Get the fee rate, in basis points, that a campaign's payouts will be charged
+    pub fn get_campaign_fee(env: Env, campaign_index: u32) -> Result<u32, Error> {
+        load_campaign(&env, campaign_index)?;
+        Ok(campaign_fee_bps(&env, campaign_index))
+    }
+
+    // Get the contribution rules of a campaign. Allowlist members are queried with `is_allowlisted`.
+    pub fn get_rules(env: Env, campaign_index: u32) -> Result<ContributionRules, Error> {
+        load_campaign(&env, campaign_index)?;
+        Ok(load_rules(&env, campaign_index).unwrap_or(ContributionRules {
+            min_contribution: None,
+            max_per_contributor: None,
+            hard_cap: None,
+            allowlist: None,
+            kyc_verifier: None,
+        }))
+    }
+
+    // Whether an address may contribute to an allowlisted campaign
+    pub fn is_allowlisted(env: Env, campaign_index: u32, address: Address) -> bool {
+        env.storage()
             .persistent()
-            .get(&symbol_short!("contributions"))
-            .unwrap_or_else(|| Map::new(&env));
+            .has(&DataKey::Allowlisted(campaign_index, address))
+    }
+
+    // Get the unit an asset was registered under, if any
+    pub fn get_asset_unit(env: Env, token: Address) -> Option<Symbol> {
+        load_asset_unit(&env, &token)
+    }
+
+    // Get the amount raised in each asset the campaign accepts
+    pub fn get_raised_by_asset(env: Env, campaign_index: u32) -> Result<Vec<AssetAmount>, Error> {
+        let campaign = load_campaign(&env, campaign_index)?;
 
-        contributions
-            .get(user)
-            .unwrap_or_else(|| Map::new(&env))
-            .get(campaign_index)
-            .unwrap_or(0)
+        let mut raised = Vec::new(&env);
+        for token in campaign.accepted_tokens.iter() {
+            let amount = load_asset_raised(&env, campaign_index, &token);
+            raised.push_back(AssetAmount { token, amount });
+        }
+        Ok(raised)
+    }
+
+    // Number of distinct addresses that have contributed to a campaign
+    pub fn get_contributor_count(env: Env, campaign_index: u32) -> Result<u32, Error> {
+        load_campaign(&env, campaign_index)?;
+        Ok(contributor_count(&env, campaign_index))
+    }
+
+    // Page through a campaign's contributors in order of their first contribution. Backers who
+    // have since been refunded stay listed with an amount of zero.
+    pub fn get_contributors(env: Env, campaign_index: u32, start: u32, limit: u32) -> Result<Vec<Backer>, Error> {
+        load_campaign(&env, campaign_index)?;
+
+        let end = contributor_count(&env, campaign_index).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut backers = Vec::new(&env);
+        for position in start..end {
+            let address = load_contributor(&env, campaign_index, position);
+            let amount = load_contribution(&env, campaign_index, &address);
+            backers.push_back(Backer { address, amount });
+        }
+        Ok(backers)
+    }
+
+    // The `count` largest backers of a campaign, largest first, up to the 20 on the leaderboard.
+    // Ties rank whoever reached the amount first ahead. Approximate after withdrawals: a backer
+    // below the list is not promoted into a freed place until they contribute again or
+    // `refresh_top_backers` re-ranks them. `get_contributors` always has exact amounts.
+    pub fn get_top_backers(env: Env, campaign_index: u32, count: u32) -> Result<Vec<Backer>, Error> {
+        load_campaign(&env, campaign_index)?;
+
+        let mut top = load_top_backers(&env, campaign_index);
+        while top.len() > count {
+            top.pop_back();
+        }
+        Ok(top)
+    }
+
+    // Get user contributions to a campaign
+    pub fn get_user_contributions(env: Env, user: Address, campaign_index: u32) -> i128 {
+        load_contribution(&env, campaign_index, &user)
+    }
+
+    // Get user contributions to a campaign broken down by asset
+    pub fn get_user_contributions_by_asset(
+        env: Env,
+        user: Address,
+        campaign_index: u32,
+    ) -> Result<Vec<AssetAmount>, Error> {
+        let campaign = load_campaign(&env, campaign_index)?;
+
+        let mut contributions = Vec::new(&env);
+        for token in campaign.accepted_tokens.iter() {
+            let amount = load_asset_contribution(&env, campaign_index, &user, &token);
+            contributions.push_back(AssetAmount { token, amount });
+        }
+        Ok(contributions)
     }
 }
+
+mod tests;
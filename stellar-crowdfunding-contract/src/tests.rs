@@ -1,61 +1,2212 @@
 #[cfg(test)]
 mod test {
-    use soroban_sdk::{Env, Address};
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke},
+        contract, contractimpl, symbol_short, token, vec, BytesN, Env, Address, IntoVal, String, Symbol,
+        TryFromVal, Val, Vec,
+    };
 
-    use crate::{CrowdfundingContract, CrowdfundingContractClient};
+    use crate::{
+        AssetAmount, Backer, CampaignEntry, CampaignOptions, CampaignStatus, ContributionRules,
+        CrowdfundingContract, CrowdfundingContractClient, Error, FundingMode, MetadataVersion, Milestone,
+        ReceiptMetadata, RewardTier, RoundAllocation,
+    };
+
+    fn options(env: &Env) -> CampaignOptions {
+        CampaignOptions {
+            milestones: Vec::new(env),
+            reward_tiers: Vec::new(env),
+            allow_unpledge: false,
+            accepted_tokens: Vec::new(env),
+            rules: no_rules(),
+            funding_mode: FundingMode::AllOrNothing,
+        }
+    }
+
+    fn content_hash(env: &Env) -> BytesN<32> {
+        BytesN::from_array(env, &[7; 32])
+    }
+
+    // Count the events published with the given name as their first topic
+    fn count_events(env: &Env, name: Symbol) -> usize {
+        env.events()
+            .all()
+            .iter()
+            .filter(|(_, topics, _)| {
+                topics
+                    .get(0)
+                    .and_then(|topic| Symbol::try_from_val(env, &topic).ok())
+                    .is_some_and(|topic| topic == name)
+            })
+            .count()
+    }
+
+    // Deploy and initialize the contract, returning the client and its admin
+    fn setup_contract<'a>(
+        env: &Env,
+    ) -> (CrowdfundingContractClient<'a>, Address, token::Client<'a>, token::AdminClient<'a>) {
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, CrowdfundingContract);
+        let client = CrowdfundingContractClient::new(env, &contract_id);
+
+        let token_address = env.register_stellar_asset_contract(Address::random(env));
+        let token_client = token::Client::new(env, &token_address);
+        let token_admin_client = token::AdminClient::new(env, &token_address);
+
+        let admin = Address::random(env);
+        client.initialize(&token_address, &admin);
+
+        (client, admin, token_client, token_admin_client)
+    }
+
+    fn setup_campaign<'a>(
+        env: &Env,
+        target_amount: i128,
+    ) -> (CrowdfundingContractClient<'a>, Address, token::Client<'a>, token::AdminClient<'a>) {
+        let (client, _admin, token_client, token_admin_client) = setup_contract(env);
+        let creator = Address::random(env);
+        client.create_campaign(
+            &creator,
+            &String::from_str(env, "Test Campaign"),
+            &content_hash(env),
+            &target_amount,
+            &(env.ledger().timestamp() + 1000),
+            &options(env),
+        );
+
+        (client, creator, token_client, token_admin_client)
+    }
 
     #[test]
     fn test_campaign_creation() {
         let env = Env::default();
+        env.mock_all_auths();
         let contract_id = env.register_contract(None, CrowdfundingContract);
         let client = CrowdfundingContractClient::new(&env, &contract_id);
 
         // Initialize the contract
-        client.initialize();
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        client.initialize(&token_address, &Address::random(&env));
 
         // Create a test campaign
         let creator = Address::random(&env);
         client.create_campaign(
             &creator,
-            "Test Campaign".to_string(),
-            "Description".to_string(),
-            1000,
-            env.ledger().timestamp() + 1000,
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &options(&env),
         );
 
         // Verify the campaign exists
-        let campaign = client.get_campaign(0);
-        assert_eq!(campaign.get(0).unwrap(), creator);
-        assert_eq!(campaign.get(1).unwrap(), "Test Campaign");
+        let campaign = client.get_campaign(&0);
+        assert_eq!(campaign.creator, creator);
+        assert_eq!(campaign.title, String::from_str(&env, "Test Campaign"));
+        assert_eq!(campaign.target_amount, 1000);
+        assert_eq!(campaign.amount_raised, 0);
+        assert_eq!(campaign.status, CampaignStatus::Active);
     }
 
     #[test]
     fn test_contribution() {
         let env = Env::default();
+        env.mock_all_auths();
         let contract_id = env.register_contract(None, CrowdfundingContract);
         let client = CrowdfundingContractClient::new(&env, &contract_id);
 
+        // Create test token
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let token_client = token::Client::new(&env, &token_address);
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
         // Initialize and create a campaign
-        client.initialize();
+        client.initialize(&token_address, &Address::random(&env));
         let creator = Address::random(&env);
         client.create_campaign(
             &creator,
-            "Test Campaign".to_string(),
-            "Description".to_string(),
-            1000,
-            env.ledger().timestamp() + 1000,
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &options(&env),
         );
 
         // Make a contribution
         let contributor = Address::random(&env);
-        client.contribute(&contributor, 0, 100);
+        token_admin_client.mint(&contributor, &500);
+        client.contribute(&contributor, &0, &token_client.address, &100, &None);
 
         // Verify the contribution was recorded
-        let contributions = client.get_user_contributions(&contributor, 0);
+        let contributions = client.get_user_contributions(&contributor, &0);
         assert_eq!(contributions, 100);
 
-        let campaign = client.get_campaign(0);
-        let amount_raised: i64 = campaign.get(6).unwrap().unwrap().try_into().unwrap();
-        assert_eq!(amount_raised, 100);
+        let campaign = client.get_campaign(&0);
+        assert_eq!(campaign.amount_raised, 100);
+
+        // Verify the tokens are held in escrow by the contract
+        assert_eq!(token_client.balance(&contributor), 400);
+        assert_eq!(token_client.balance(&contract_id), 100);
+    }
+
+    #[test]
+    fn test_refund_after_missed_target() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &500);
+        client.contribute(&contributor, &0, &token_client.address, &300, &None);
+
+        // Refunds are not available while the campaign is running
+        assert_eq!(client.try_refund(&contributor, &0), Err(Ok(Error::DeadlineNotPassed)));
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Failed);
+        client.refund(&contributor, &0);
+
+        assert_eq!(token_client.balance(&contributor), 500);
+        assert_eq!(client.get_user_contributions(&contributor, &0), 0);
+
+        // A second refund is rejected
+        assert_eq!(client.try_refund(&contributor, &0), Err(Ok(Error::NothingToRefund)));
+    }
+
+    #[test]
+    fn test_no_refund_when_target_met() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.try_refund(&contributor, &0), Err(Ok(Error::TargetReached)));
+        assert_eq!(client.get_user_contributions(&contributor, &0), 1000);
+    }
+
+    #[test]
+    fn test_claim_successful_campaign() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1200);
+        client.contribute(&contributor, &0, &token_client.address, &1200, &None);
+
+        // Funds stay in escrow until the deadline
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::DeadlineNotPassed)));
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+
+        // Only the creator can claim
+        assert_eq!(client.try_claim(&contributor, &0), Err(Ok(Error::Unauthorized)));
+
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Successful);
+        client.claim(&creator, &0);
+        assert_eq!(token_client.balance(&creator), 1200);
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Claimed);
+
+        // The payout cannot be claimed twice
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::AlreadyClaimed)));
+    }
+
+    #[test]
+    fn test_no_claim_when_target_missed() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &500);
+        client.contribute(&contributor, &0, &token_client.address, &500, &None);
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::TargetNotReached)));
+        assert_eq!(token_client.balance(&creator), 0);
+    }
+
+    #[test]
+    fn test_campaigns_are_stored_independently() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let second_creator = Address::random(&env);
+        let campaign_index = client.create_campaign(
+            &second_creator,
+            &String::from_str(&env, "Second Campaign"),
+            &content_hash(&env),
+            &2000,
+            &(env.ledger().timestamp() + 2000),
+            &options(&env),
+        );
+        assert_eq!(campaign_index, 1);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &500);
+        client.contribute(&contributor, &1, &token_client.address, &500, &None);
+
+        assert_eq!(client.get_campaign(&0).amount_raised, 0);
+        assert_eq!(client.get_campaign(&1).amount_raised, 500);
+        assert_eq!(client.get_campaign(&1).creator, second_creator);
+        assert_eq!(client.get_user_contributions(&contributor, &0), 0);
+        assert_eq!(client.get_user_contributions(&contributor, &1), 500);
+
+        // Unknown campaigns are rejected
+        assert_eq!(client.try_get_campaign(&2), Err(Ok(Error::NotFound)));
+    }
+
+    #[test]
+    fn test_unauthorized_calls_fail() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        let attacker = Address::random(&env);
+        token_admin_client.mint(&contributor, &500);
+
+        // Spending the contributor's tokens requires the contributor's signature. The auth failure is
+        // a host error rather than one of the contract's own error codes.
+        let contribute_args: Vec<Val> =
+            (&contributor, 0u32, &token_client.address, 100i128, None::<u32>).into_val(&env);
+        let transfer = MockAuthInvoke {
+            contract: &token_client.address,
+            fn_name: "transfer",
+            args: (&contributor, &client.address, 100i128).into_val(&env),
+            sub_invokes: &[],
+        };
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "contribute",
+                args: contribute_args.clone(),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(matches!(
+            client.try_contribute(&contributor, &0, &token_client.address, &100, &None),
+            Err(Err(_))
+        ));
+        assert_eq!(token_client.balance(&contributor), 500);
+        assert_eq!(client.get_user_contributions(&contributor, &0), 0);
+
+        // The same contribution signed by the contributor goes through
+        env.mock_auths(&[MockAuth {
+            address: &contributor,
+            invoke: &MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "contribute",
+                args: contribute_args,
+                sub_invokes: &[transfer],
+            },
+        }]);
+        client.contribute(&contributor, &0, &token_client.address, &100, &None);
+        assert_eq!(client.get_user_contributions(&contributor, &0), 100);
+
+        // Claiming on the creator's behalf requires the creator's signature
+        env.mock_all_auths();
+        token_admin_client.mint(&contributor, &600);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "claim",
+                args: (&creator, 0u32).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_claim(&creator, &0).is_err());
+        assert_eq!(token_client.balance(&creator), 0);
+    }
+
+    #[test]
+    fn test_invalid_campaign_and_contribution() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let title = String::from_str(&env, "Bad Campaign");
+        let hash = content_hash(&env);
+        let now = env.ledger().timestamp();
+        assert_eq!(
+            client.try_create_campaign(&creator, &title, &hash, &1000, &now, &options(&env)),
+            Err(Ok(Error::InvalidDeadline))
+        );
+        assert_eq!(
+            client.try_create_campaign(&creator, &title, &hash, &0, &(now + 1000), &options(&env)),
+            Err(Ok(Error::InvalidAmount))
+        );
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &500);
+        assert_eq!(
+            client.try_contribute(&contributor, &0, &token_client.address, &0, &None),
+            Err(Ok(Error::InvalidAmount))
+        );
+        assert_eq!(
+            client.try_contribute(&contributor, &7, &token_client.address, &100, &None),
+            Err(Ok(Error::NotFound))
+        );
+
+        env.ledger().set_timestamp(now + 1000);
+        assert_eq!(
+            client.try_contribute(&contributor, &0, &token_client.address, &100, &None),
+            Err(Ok(Error::DeadlinePassed))
+        );
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        let (client, creator, _token_client, _token_admin_client) = setup_campaign(&env, 1000);
+
+        // Re-initializing cannot swap the token or wipe existing campaigns
+        let other_token = env.register_stellar_asset_contract(Address::random(&env));
+        assert_eq!(
+            client.try_initialize(&other_token, &Address::random(&env)),
+            Err(Ok(Error::AlreadyInitialized))
+        );
+        assert_eq!(client.get_campaign(&0).creator, creator);
+    }
+
+    #[test]
+    fn test_calls_before_initialize_fail() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, CrowdfundingContract);
+        let client = CrowdfundingContractClient::new(&env, &contract_id);
+
+        let creator = Address::random(&env);
+        assert_eq!(
+            client.try_create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &options(&env),
+            ),
+            Err(Ok(Error::NotInitialized))
+        );
+        assert_eq!(
+            client.try_contribute(&creator, &0, &Address::random(&env), &100, &None),
+            Err(Ok(Error::NotInitialized))
+        );
+    }
+
+    #[test]
+    fn test_milestones() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let milestones = vec![
+            &env,
+            Milestone { amount: 1000, label: String::from_str(&env, "Base goal") },
+            Milestone { amount: 1500, label: String::from_str(&env, "Stretch goal") },
+            Milestone { amount: 2500, label: String::from_str(&env, "Super stretch") },
+        ];
+        let campaign_index = client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Staged Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &CampaignOptions { milestones, ..options(&env) },
+        );
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &3000);
+
+        client.contribute(&contributor, &campaign_index, &token_client.address, &900, &None);
+        let states = client.get_milestones(&campaign_index);
+        assert_eq!(states.len(), 3);
+        assert!(!states.get(0).unwrap().reached);
+
+        // A single contribution can cross several milestones at once
+        client.contribute(&contributor, &campaign_index, &token_client.address, &700, &None);
+        assert_eq!(count_events(&env, symbol_short!("MILESTONE")), 2);
+
+        let states = client.get_milestones(&campaign_index);
+        assert!(states.get(0).unwrap().reached);
+        assert!(states.get(1).unwrap().reached);
+        assert!(!states.get(2).unwrap().reached);
+        assert_eq!(states.get(1).unwrap().label, String::from_str(&env, "Stretch goal"));
+    }
+
+    #[test]
+    fn test_milestones_must_increase() {
+        let env = Env::default();
+        let (client, creator, _token_client, _token_admin_client) = setup_campaign(&env, 1000);
+
+        let milestones = vec![
+            &env,
+            Milestone { amount: 1500, label: String::from_str(&env, "Stretch goal") },
+            Milestone { amount: 1000, label: String::from_str(&env, "Base goal") },
+        ];
+        assert_eq!(
+            client.try_create_campaign(
+                &creator,
+                &String::from_str(&env, "Staged Campaign"),
+                &content_hash(&env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &CampaignOptions { milestones, ..options(&env) },
+            ),
+            Err(Ok(Error::InvalidCampaign))
+        );
+    }
+
+    #[test]
+    fn test_tranches_released_by_contributor_approval() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        client.set_tranches(&creator, &0, &vec![&env, 5000, 5000], &None);
+
+        let backer1 = Address::random(&env);
+        let backer2 = Address::random(&env);
+        token_admin_client.mint(&backer1, &600);
+        token_admin_client.mint(&backer2, &400);
+        client.contribute(&backer1, &0, &token_client.address, &600, &None);
+        client.contribute(&backer2, &0, &token_client.address, &400, &None);
+
+        // The schedule is locked once contributions have started
+        assert_eq!(
+            client.try_set_tranches(&creator, &0, &vec![&env, 10000], &None),
+            Err(Ok(Error::ContributionsStarted))
+        );
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::TranchedPayout)));
+        assert_eq!(client.try_release_tranche(&creator, &0), Err(Ok(Error::InvalidTrancheRequest)));
+
+        client.request_tranche(&creator, &0);
+        assert_eq!(client.try_request_tranche(&creator, &0), Err(Ok(Error::InvalidTrancheRequest)));
+        client.approve_tranche(&backer2, &0);
+        assert_eq!(client.try_approve_tranche(&backer2, &0), Err(Ok(Error::AlreadyVoted)));
+
+        // 400 of 1000 is not a majority
+        assert_eq!(client.try_release_tranche(&backer2, &0), Err(Ok(Error::TrancheNotApproved)));
+
+        client.approve_tranche(&backer1, &0);
+        client.release_tranche(&backer2, &0);
+        assert_eq!(token_client.balance(&creator), 500);
+        assert_eq!(count_events(&env, symbol_short!("TRANCHREL")), 1);
+
+        // Approvals do not carry over to the next tranche
+        client.request_tranche(&creator, &0);
+        assert_eq!(client.try_release_tranche(&creator, &0), Err(Ok(Error::TrancheNotApproved)));
+        client.approve_tranche(&backer1, &0);
+        client.release_tranche(&creator, &0);
+        assert_eq!(token_client.balance(&creator), 1000);
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Claimed);
+    }
+
+    #[test]
+    fn test_tranches_stopped_and_reclaimed() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let arbiter = Address::random(&env);
+        client.set_tranches(&creator, &0, &vec![&env, 3000, 7000], &Some(arbiter.clone()));
+
+        let backer1 = Address::random(&env);
+        let backer2 = Address::random(&env);
+        token_admin_client.mint(&backer1, &600);
+        token_admin_client.mint(&backer2, &400);
+        client.contribute(&backer1, &0, &token_client.address, &600, &None);
+        client.contribute(&backer2, &0, &token_client.address, &400, &None);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+
+        // The arbiter can release a requested tranche without a vote
+        client.request_tranche(&creator, &0);
+        client.release_tranche(&arbiter, &0);
+        assert_eq!(token_client.balance(&creator), 300);
+
+        assert_eq!(client.try_reclaim(&backer1, &0), Err(Ok(Error::TranchesNotStopped)));
+        client.vote_stop_tranches(&backer1, &0);
+        assert!(client.get_tranches(&0).stopped);
+        assert_eq!(client.try_request_tranche(&creator, &0), Err(Ok(Error::TranchesStopped)));
+
+        // The unreleased 700 goes back pro rata
+        client.reclaim(&backer1, &0);
+        client.reclaim(&backer2, &0);
+        assert_eq!(token_client.balance(&backer1), 420);
+        assert_eq!(token_client.balance(&backer2), 280);
+        assert_eq!(client.try_reclaim(&backer1, &0), Err(Ok(Error::NothingToRefund)));
+    }
+
+    #[test]
+    fn test_invalid_tranches() {
+        let env = Env::default();
+        let (client, creator, _token_client, _token_admin_client) = setup_campaign(&env, 1000);
+
+        assert_eq!(
+            client.try_set_tranches(&creator, &0, &vec![&env, 5000, 4000], &None),
+            Err(Ok(Error::InvalidTranches))
+        );
+        assert_eq!(
+            client.try_set_tranches(&creator, &0, &vec![&env, 10000, 0], &None),
+            Err(Ok(Error::InvalidTranches))
+        );
+        let outsider = Address::random(&env);
+        assert_eq!(
+            client.try_set_tranches(&outsider, &0, &vec![&env, 10000], &None),
+            Err(Ok(Error::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_reward_tiers() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        // A tier's supply is capped at 100
+        let oversized = vec![
+            &env,
+            RewardTier { min_amount: 100, quantity: 101, description: String::from_str(&env, "Sticker") },
+        ];
+        assert_eq!(
+            client.try_create_campaign(
+                &creator,
+                &String::from_str(&env, "Perk Campaign"),
+                &content_hash(&env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &CampaignOptions { reward_tiers: oversized, ..options(&env) },
+            ),
+            Err(Ok(Error::InvalidRewardTiers))
+        );
+
+        let reward_tiers = vec![
+            &env,
+            RewardTier { min_amount: 100, quantity: 2, description: String::from_str(&env, "Sticker") },
+            RewardTier { min_amount: 500, quantity: 1, description: String::from_str(&env, "T-shirt") },
+        ];
+        let campaign_index = client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Perk Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &CampaignOptions { reward_tiers, ..options(&env) },
+        );
+
+        let backer1 = Address::random(&env);
+        let backer2 = Address::random(&env);
+        let backer3 = Address::random(&env);
+        for backer in [&backer1, &backer2, &backer3] {
+            token_admin_client.mint(backer, &1000);
+        }
+
+        assert_eq!(
+            client.try_contribute(&backer1, &campaign_index, &token_client.address, &400, &Some(1)),
+            Err(Ok(Error::BelowTierMinimum))
+        );
+        assert_eq!(
+            client.try_contribute(&backer1, &campaign_index, &token_client.address, &400, &Some(2)),
+            Err(Ok(Error::InvalidTier))
+        );
+
+        client.contribute(&backer1, &campaign_index, &token_client.address, &500, &Some(1));
+        client.contribute(&backer1, &campaign_index, &token_client.address, &100, &Some(0));
+        assert_eq!(
+            client.try_contribute(&backer1, &campaign_index, &token_client.address, &100, &Some(0)),
+            Err(Ok(Error::InvalidTier))
+        );
+        assert_eq!(
+            client.try_contribute(&backer2, &campaign_index, &token_client.address, &500, &Some(1)),
+            Err(Ok(Error::TierSoldOut))
+        );
+        client.contribute(&backer2, &campaign_index, &token_client.address, &150, &Some(0));
+        assert_eq!(
+            client.try_contribute(&backer3, &campaign_index, &token_client.address, &100, &Some(0)),
+            Err(Ok(Error::TierSoldOut))
+        );
+
+        // A rejected tier selection does not take any funds
+        assert_eq!(client.get_user_contributions(&backer3, &campaign_index), 0);
+
+        let tiers = client.get_reward_tiers(&campaign_index);
+        assert_eq!(tiers.get(0).unwrap().remaining, 0);
+        assert_eq!(tiers.get(1).unwrap().remaining, 0);
+        assert_eq!(client.get_backers_for_tier(&campaign_index, &0), vec![&env, backer1.clone(), backer2]);
+        assert_eq!(client.get_backers_for_tier(&campaign_index, &1), vec![&env, backer1]);
+        assert_eq!(client.try_get_backers_for_tier(&campaign_index, &2), Err(Ok(Error::InvalidTier)));
+    }
+
+    #[test]
+    fn test_unpledge_before_deadline() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let reward_tiers = vec![
+            &env,
+            RewardTier { min_amount: 300, quantity: 1, description: String::from_str(&env, "Poster") },
+        ];
+        let deadline = env.ledger().timestamp() + 1000;
+        let campaign_index = client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Flexible Campaign"),
+            &content_hash(&env),
+            &1000,
+            &deadline,
+            &CampaignOptions { reward_tiers, allow_unpledge: true, ..options(&env) },
+        );
+
+        let contributor = Address::random(&env);
+        let other = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        token_admin_client.mint(&other, &1000);
+        client.contribute(&contributor, &campaign_index, &token_client.address, &500, &Some(0));
+
+        assert_eq!(
+            client.try_unpledge(&contributor, &campaign_index, &token_client.address, &0),
+            Err(Ok(Error::InvalidAmount))
+        );
+        assert_eq!(
+            client.try_unpledge(&contributor, &campaign_index, &token_client.address, &501),
+            Err(Ok(Error::ExceedsContribution))
+        );
+        assert_eq!(
+            client.try_unpledge(&other, &campaign_index, &token_client.address, &1),
+            Err(Ok(Error::ExceedsContribution))
+        );
+
+        // Dropping below the tier minimum gives the perk back to the pool
+        client.unpledge(&contributor, &campaign_index, &token_client.address, &300);
+        assert_eq!(client.get_user_contributions(&contributor, &campaign_index), 200);
+        assert_eq!(client.get_campaign(&campaign_index).amount_raised, 200);
+        assert_eq!(token_client.balance(&contributor), 800);
+        assert_eq!(token_client.balance(&client.address), 200);
+        assert_eq!(count_events(&env, symbol_short!("UNPLEDGE")), 1);
+        assert_eq!(client.get_backers_for_tier(&campaign_index, &0).len(), 0);
+        client.contribute(&other, &campaign_index, &token_client.address, &300, &Some(0));
+
+        // Pledges are locked in once the deadline passes
+        env.ledger().set_timestamp(deadline);
+        assert_eq!(
+            client.try_unpledge(&contributor, &campaign_index, &token_client.address, &200),
+            Err(Ok(Error::DeadlinePassed))
+        );
+    }
+
+    #[test]
+    fn test_unpledge_disabled_by_default() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &500, &None);
+
+        assert_eq!(
+            client.try_unpledge(&contributor, &0, &token_client.address, &100),
+            Err(Ok(Error::UnpledgeDisabled))
+        );
+        assert_eq!(client.get_campaign(&0).amount_raised, 500);
+        assert_eq!(token_client.balance(&contributor), 500);
+    }
+
+    #[test]
+    fn test_cancel_campaign_refunds_everyone() {
+        let env = Env::default();
+        let (client, admin, token_client, token_admin_client) = setup_contract(&env);
+
+        let creator = Address::random(&env);
+        let outsider = Address::random(&env);
+        let deadline = env.ledger().timestamp() + 1000;
+        for _ in 0..2 {
+            client.create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &deadline,
+                &options(&env),
+            );
+        }
+
+        let contributor1 = Address::random(&env);
+        let contributor2 = Address::random(&env);
+        token_admin_client.mint(&contributor1, &1000);
+        token_admin_client.mint(&contributor2, &1000);
+        client.contribute(&contributor1, &0, &token_client.address, &600, &None);
+        client.contribute(&contributor2, &0, &token_client.address, &400, &None);
+        client.contribute(&contributor1, &1, &token_client.address, &100, &None);
+
+        // Only the creator or the admin may cancel
+        assert_eq!(client.try_cancel_campaign(&outsider, &0), Err(Ok(Error::Unauthorized)));
+
+        // The target is already met, but cancelling still opens refunds before the deadline
+        client.cancel_campaign(&creator, &0);
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Cancelled);
+        assert_eq!(count_events(&env, symbol_short!("CANCELLED")), 1);
+        assert_eq!(client.try_cancel_campaign(&admin, &0), Err(Ok(Error::CampaignCancelled)));
+        assert_eq!(
+            client.try_contribute(&contributor1, &0, &token_client.address, &100, &None),
+            Err(Ok(Error::CampaignCancelled))
+        );
+
+        client.refund(&contributor1, &0);
+        client.refund(&contributor2, &0);
+        assert_eq!(token_client.balance(&contributor1), 900);
+        assert_eq!(token_client.balance(&contributor2), 1000);
+        assert_eq!(client.try_refund(&contributor1, &0), Err(Ok(Error::NothingToRefund)));
+
+        // The admin can cancel someone else's campaign, and nothing is left to claim afterwards
+        client.cancel_campaign(&admin, &1);
+        env.ledger().set_timestamp(deadline);
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::CampaignCancelled)));
+        client.refund(&contributor1, &1);
+        assert_eq!(token_client.balance(&contributor1), 1000);
+        assert_eq!(token_client.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_cannot_cancel_after_success() {
+        let env = Env::default();
+        let (client, admin, token_client, token_admin_client) = setup_contract(&env);
+        let creator = Address::random(&env);
+        client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &options(&env),
+        );
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+
+        // Once the deadline passes with the target met, the payout can't be turned into refunds
+        assert_eq!(client.try_cancel_campaign(&admin, &0), Err(Ok(Error::TargetReached)));
+        assert_eq!(client.try_cancel_campaign(&creator, &0), Err(Ok(Error::TargetReached)));
+        client.claim(&creator, &0);
+
+        assert_eq!(client.try_cancel_campaign(&creator, &0), Err(Ok(Error::AlreadyClaimed)));
+        assert_eq!(client.try_refund(&contributor, &0), Err(Ok(Error::TargetReached)));
+    }
+
+    #[test]
+    fn test_update_campaign_opens_opt_out_window() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let early_backer = Address::random(&env);
+        let late_backer = Address::random(&env);
+        token_admin_client.mint(&early_backer, &1000);
+        token_admin_client.mint(&late_backer, &1000);
+        client.contribute(&early_backer, &0, &token_client.address, &400, &None);
+
+        // Nobody can opt out before the terms change
+        assert_eq!(client.try_opt_out(&early_backer, &0), Err(Ok(Error::OptOutClosed)));
+
+        let campaign = client.get_campaign(&0);
+        let new_deadline = campaign.deadline + 7 * 24 * 60 * 60;
+        client.update_campaign(&creator, &0, &2000, &new_deadline);
+        assert_eq!(count_events(&env, symbol_short!("CAMPUPDT")), 1);
+
+        let campaign = client.get_campaign(&0);
+        assert_eq!(campaign.target_amount, 2000);
+        assert_eq!(campaign.deadline, new_deadline);
+        assert_eq!(campaign.terms_version, 1);
+
+        // Pledging after the change accepts the new terms
+        client.contribute(&late_backer, &0, &token_client.address, &300, &None);
+        assert_eq!(client.try_opt_out(&late_backer, &0), Err(Ok(Error::TermsAccepted)));
+
+        client.opt_out(&early_backer, &0);
+        assert_eq!(token_client.balance(&early_backer), 1000);
+        assert_eq!(client.get_campaign(&0).amount_raised, 300);
+        assert_eq!(count_events(&env, symbol_short!("OPTOUT")), 1);
+        assert_eq!(client.try_opt_out(&early_backer, &0), Err(Ok(Error::NothingToRefund)));
+
+        // The window closes after a fixed period
+        client.contribute(&early_backer, &0, &token_client.address, &100, &None);
+        client.update_campaign(&creator, &0, &1500, &new_deadline);
+        env.ledger().set_timestamp(campaign.opt_out_until);
+        assert_eq!(client.try_opt_out(&late_backer, &0), Err(Ok(Error::OptOutClosed)));
+    }
+
+    #[test]
+    fn test_update_campaign_limits() {
+        let env = Env::default();
+        let (client, admin, token_client, token_admin_client) = setup_contract(&env);
+
+        let creator = Address::random(&env);
+        let deadline = env.ledger().timestamp() + 1000;
+        client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &1000,
+            &deadline,
+            &options(&env),
+        );
+
+        let contributor = Address::random(&env);
+        let outsider = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &600, &None);
+
+        let extended = deadline + 5 * 24 * 60 * 60;
+        assert_eq!(client.try_update_campaign(&outsider, &0, &1000, &extended), Err(Ok(Error::Unauthorized)));
+        assert_eq!(
+            client.try_update_campaign(&creator, &0, &500, &extended),
+            Err(Ok(Error::TargetBelowRaised))
+        );
+        assert_eq!(
+            client.try_update_campaign(&creator, &0, &1000, &(deadline - 1)),
+            Err(Ok(Error::InvalidDeadline))
+        );
+
+        // The deadline must leave room for the opt-out window
+        assert_eq!(
+            client.try_update_campaign(&creator, &0, &800, &deadline),
+            Err(Ok(Error::InvalidDeadline))
+        );
+
+        // Extensions are capped relative to the original deadline, not the latest one
+        let max_extension = 10 * 24 * 60 * 60;
+        assert_eq!(client.try_set_max_extension(&outsider, &max_extension), Err(Ok(Error::Unauthorized)));
+        client.set_max_extension(&admin, &max_extension);
+        client.update_campaign(&creator, &0, &800, &extended);
+        assert_eq!(
+            client.try_update_campaign(&creator, &0, &800, &(deadline + max_extension + 1)),
+            Err(Ok(Error::ExtensionTooLong))
+        );
+        client.update_campaign(&creator, &0, &800, &(deadline + max_extension));
+
+        env.ledger().set_timestamp(deadline + max_extension);
+        assert_eq!(
+            client.try_update_campaign(&creator, &0, &700, &(deadline + max_extension)),
+            Err(Ok(Error::DeadlinePassed))
+        );
+    }
+
+    // Create a campaign that accepts the contract's token plus a second asset
+    fn setup_two_asset_campaign<'a>(
+        env: &Env,
+        target_amount: i128,
+    ) -> (CrowdfundingContractClient<'a>, Address, token::AdminClient<'a>, token::AdminClient<'a>) {
+        let (client, admin, token_client, token_admin_client) = setup_contract(env);
+
+        let usdc_address = env.register_stellar_asset_contract(Address::random(env));
+        let usdc_admin_client = token::AdminClient::new(env, &usdc_address);
+        let usd = Some(symbol_short!("USD"));
+        client.set_asset_unit(&admin, &token_client.address, &usd);
+        client.set_asset_unit(&admin, &usdc_address, &usd);
+
+        let creator = Address::random(env);
+        client.create_campaign(
+            &creator,
+            &String::from_str(env, "Two Asset Campaign"),
+            &content_hash(env),
+            &target_amount,
+            &(env.ledger().timestamp() + 1000),
+            &CampaignOptions {
+                accepted_tokens: vec![env, token_client.address.clone(), usdc_address],
+                ..options(env)
+            },
+        );
+
+        (client, creator, token_admin_client, usdc_admin_client)
+    }
+
+    #[test]
+    fn test_multi_asset_claim() {
+        let env = Env::default();
+        let (client, creator, usdt_admin_client, usdc_admin_client) = setup_two_asset_campaign(&env, 1000);
+        let usdt = usdt_admin_client.address.clone();
+        let usdc = usdc_admin_client.address.clone();
+        let usdt_client = token::Client::new(&env, &usdt);
+        let usdc_client = token::Client::new(&env, &usdc);
+
+        let contributor1 = Address::random(&env);
+        let contributor2 = Address::random(&env);
+        usdt_admin_client.mint(&contributor1, &1000);
+        usdc_admin_client.mint(&contributor1, &1000);
+        usdc_admin_client.mint(&contributor2, &1000);
+
+        client.contribute(&contributor1, &0, &usdt, &300, &None);
+        client.contribute(&contributor1, &0, &usdc, &200, &None);
+        client.contribute(&contributor2, &0, &usdc, &500, &None);
+
+        // Assets outside the whitelist are rejected
+        let other_token = env.register_stellar_asset_contract(Address::random(&env));
+        assert_eq!(
+            client.try_contribute(&contributor1, &0, &other_token, &100, &None),
+            Err(Ok(Error::TokenNotAccepted))
+        );
+
+        assert_eq!(client.get_campaign(&0).amount_raised, 1000);
+        assert_eq!(
+            client.get_raised_by_asset(&0),
+            vec![
+                &env,
+                AssetAmount { token: usdt.clone(), amount: 300 },
+                AssetAmount { token: usdc.clone(), amount: 700 },
+            ]
+        );
+        assert_eq!(client.get_user_contributions(&contributor1, &0), 500);
+        assert_eq!(
+            client.get_user_contributions_by_asset(&contributor2, &0),
+            vec![
+                &env,
+                AssetAmount { token: usdt.clone(), amount: 0 },
+                AssetAmount { token: usdc.clone(), amount: 500 },
+            ]
+        );
+
+        // The creator is paid in each asset that was raised
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        client.claim(&creator, &0);
+        assert_eq!(usdt_client.balance(&creator), 300);
+        assert_eq!(usdc_client.balance(&creator), 700);
+        assert_eq!(count_events(&env, symbol_short!("CLAIMED")), 2);
+    }
+
+    #[test]
+    fn test_multi_asset_refund() {
+        let env = Env::default();
+        let (client, _creator, usdt_admin_client, usdc_admin_client) = setup_two_asset_campaign(&env, 5000);
+        let usdt = usdt_admin_client.address.clone();
+        let usdc = usdc_admin_client.address.clone();
+        let usdt_client = token::Client::new(&env, &usdt);
+        let usdc_client = token::Client::new(&env, &usdc);
+
+        let contributor = Address::random(&env);
+        usdt_admin_client.mint(&contributor, &1000);
+        usdc_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &usdt, &400, &None);
+        client.contribute(&contributor, &0, &usdc, &600, &None);
+
+        // Refunds come back in the asset each contribution was made in
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        client.refund(&contributor, &0);
+        assert_eq!(usdt_client.balance(&contributor), 1000);
+        assert_eq!(usdc_client.balance(&contributor), 1000);
+        assert_eq!(count_events(&env, symbol_short!("REFUND")), 2);
+        assert_eq!(client.try_refund(&contributor, &0), Err(Ok(Error::NothingToRefund)));
+    }
+
+    #[test]
+    fn test_invalid_accepted_tokens() {
+        let env = Env::default();
+        let (client, admin, token_client, _token_admin_client) = setup_contract(&env);
+        let eurc_address = env.register_stellar_asset_contract(Address::random(&env));
+        let usdt_address = env.register_stellar_asset_contract(Address::random(&env));
+
+        let creator = Address::random(&env);
+        let create = |accepted_tokens: Vec<Address>| {
+            client.try_create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &CampaignOptions { accepted_tokens, ..options(&env) },
+            )
+        };
+        let duplicated = vec![&env, token_client.address.clone(), token_client.address.clone()];
+        assert_eq!(create(duplicated), Err(Ok(Error::InvalidTokens)));
+
+        // Assets are only added up together once the admin has put them under the same unit
+        let usd = vec![&env, token_client.address.clone(), usdt_address.clone()];
+        assert_eq!(create(usd.clone()), Err(Ok(Error::InvalidTokens)));
+        client.set_asset_unit(&admin, &token_client.address, &Some(symbol_short!("USD")));
+        client.set_asset_unit(&admin, &eurc_address, &Some(symbol_short!("EUR")));
+        let mixed = vec![&env, token_client.address.clone(), eurc_address.clone()];
+        assert_eq!(create(mixed), Err(Ok(Error::InvalidTokens)));
+        client.set_asset_unit(&admin, &usdt_address, &Some(symbol_short!("USD")));
+        assert!(create(usd).is_ok());
+
+        let stranger = Address::random(&env);
+        assert_eq!(
+            client.try_set_asset_unit(&stranger, &eurc_address, &None),
+            Err(Ok(Error::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_list_and_filter_campaigns() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        assert_eq!(client.campaign_count(), 0);
+        assert_eq!(client.list_campaigns(&0, &10).len(), 0);
+
+        // Alice runs campaigns 0, 2 and 4 with a short deadline, Bob runs 1 and 3 with a long one
+        let alice = Address::random(&env);
+        let bob = Address::random(&env);
+        let now = env.ledger().timestamp();
+        for campaign_index in 0..5u32 {
+            let (creator, deadline) = if campaign_index % 2 == 0 {
+                (&alice, now + 100)
+            } else {
+                (&bob, now + 1000)
+            };
+            client.create_campaign(
+                creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &deadline,
+                &options(&env),
+            );
+        }
+        assert_eq!(client.campaign_count(), 5);
+
+        let page = client.list_campaigns(&1, &2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page.get(0).unwrap().index, 1);
+        assert_eq!(page.get(1).unwrap().index, 2);
+        assert_eq!(page.get(1).unwrap().campaign.creator, alice);
+        assert_eq!(client.list_campaigns(&4, &10).len(), 1);
+        assert_eq!(client.list_campaigns(&9, &10).len(), 0);
+
+        let indexes = |entries: Vec<CampaignEntry>| {
+            let mut indexes = Vec::new(&env);
+            for entry in entries.iter() {
+                indexes.push_back(entry.index);
+            }
+            indexes
+        };
+        assert_eq!(indexes(client.list_campaigns_by_creator(&alice, &0, &10)), vec![&env, 0, 2, 4]);
+        assert_eq!(indexes(client.list_campaigns_by_creator(&alice, &1, &1)), vec![&env, 2]);
+        assert_eq!(indexes(client.list_campaigns_by_creator(&bob, &5, &10)), Vec::new(&env));
+
+        // Once Alice's deadlines pass her campaigns await settlement until claimed or cancelled
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+        env.ledger().set_timestamp(now + 100);
+        assert_eq!(indexes(client.list_active_campaigns(&0, &10).entries), vec![&env, 1, 3]);
+        assert_eq!(indexes(client.list_active_campaigns(&2, &10).entries), vec![&env, 3]);
+        assert_eq!(indexes(client.list_awaiting_settlement(&0, &10).entries), vec![&env, 0, 2, 4]);
+
+        // A full page hands back where to continue from
+        let page = client.list_awaiting_settlement(&0, &2);
+        assert_eq!(indexes(page.entries), vec![&env, 0, 2]);
+        assert_eq!(page.next_start, Some(3));
+        let page = client.list_awaiting_settlement(&3, &2);
+        assert_eq!(indexes(page.entries), vec![&env, 4]);
+        assert_eq!(page.next_start, None);
+
+        client.claim(&alice, &0);
+        client.cancel_campaign(&alice, &2);
+        assert_eq!(indexes(client.list_awaiting_settlement(&0, &10).entries), vec![&env, 4]);
+    }
+
+    #[test]
+    fn test_filtered_listing_caps_campaigns_scanned() {
+        let env = Env::default();
+        let (client, _admin, _token_client, _token_admin_client) = setup_contract(&env);
+
+        let creator = Address::random(&env);
+        let now = env.ledger().timestamp();
+        for campaign_index in 0..105u32 {
+            let deadline = if campaign_index == 104 { now + 1000 } else { now + 100 };
+            client.create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &deadline,
+                &options(&env),
+            );
+        }
+        env.ledger().set_timestamp(now + 100);
+
+        // Only the last campaign is open, beyond what one call reads
+        let page = client.list_active_campaigns(&0, &10);
+        assert_eq!(page.entries.len(), 0);
+        assert_eq!(page.next_start, Some(100));
+        let page = client.list_active_campaigns(&100, &10);
+        assert_eq!(page.entries.get(0).unwrap().index, 104);
+        assert_eq!(page.next_start, None);
+    }
+
+    #[test]
+    fn test_contributor_enumeration_and_top_backers() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 10_000);
+
+        let backers: [Address; 4] = core::array::from_fn(|_| Address::random(&env));
+        for backer in backers.iter() {
+            token_admin_client.mint(backer, &1000);
+        }
+        client.contribute(&backers[0], &0, &token_client.address, &100, &None);
+        client.contribute(&backers[1], &0, &token_client.address, &400, &None);
+        client.contribute(&backers[2], &0, &token_client.address, &250, &None);
+        client.contribute(&backers[3], &0, &token_client.address, &400, &None);
+        // A repeat contribution does not list the backer twice
+        client.contribute(&backers[0], &0, &token_client.address, &200, &None);
+
+        assert_eq!(client.get_contributor_count(&0), 4);
+        assert_eq!(
+            client.get_contributors(&0, &1, &2),
+            vec![
+                &env,
+                Backer { address: backers[1].clone(), amount: 400 },
+                Backer { address: backers[2].clone(), amount: 250 },
+            ]
+        );
+        assert_eq!(client.get_contributors(&0, &3, &10).len(), 1);
+        assert_eq!(client.get_contributors(&0, &4, &10).len(), 0);
+
+        assert_eq!(
+            client.get_top_backers(&0, &3),
+            vec![
+                &env,
+                Backer { address: backers[1].clone(), amount: 400 },
+                Backer { address: backers[3].clone(), amount: 400 },
+                Backer { address: backers[0].clone(), amount: 300 },
+            ]
+        );
+        assert_eq!(client.get_top_backers(&0, &10).len(), 4);
+
+        // Cancelled campaigns are refunded, and refunded backers drop off the leaderboard
+        client.cancel_campaign(&creator, &0);
+        client.refund(&backers[1], &0);
+        assert_eq!(
+            client.get_top_backers(&0, &1),
+            vec![&env, Backer { address: backers[3].clone(), amount: 400 }]
+        );
+        assert_eq!(client.get_contributor_count(&0), 4);
+        assert_eq!(client.get_contributors(&0, &0, &10).get(1).unwrap().amount, 0);
+        assert_eq!(client.try_get_top_backers(&7, &3), Err(Ok(Error::NotFound)));
+    }
+
+    #[test]
+    fn test_top_backers_kept_to_leaderboard_size() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        client.create_campaign(
+            &Address::random(&env),
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &100_000,
+            &(env.ledger().timestamp() + 1000),
+            &CampaignOptions { allow_unpledge: true, ..options(&env) },
+        );
+
+        // 21 backers giving 1..=21; the smallest misses the 20-place leaderboard
+        for amount in 1..=21i128 {
+            let backer = Address::random(&env);
+            token_admin_client.mint(&backer, &amount);
+            client.contribute(&backer, &0, &token_client.address, &amount, &None);
+        }
+        let top = client.get_top_backers(&0, &50);
+        assert_eq!(top.len(), 20);
+        assert_eq!(top.get(0).unwrap().amount, 21);
+        assert_eq!(top.get(19).unwrap().amount, 2);
+        assert_eq!(client.get_contributor_count(&0), 21);
+        assert_eq!(client.get_contributors(&0, &0, &1).get(0).unwrap().amount, 1);
+
+        // When the top backer withdraws, the smallest backer is left off until a refresh
+        let largest = top.get(0).unwrap().address;
+        client.unpledge(&largest, &0, &token_client.address, &21);
+        let top = client.get_top_backers(&0, &50);
+        assert_eq!(top.len(), 19);
+        assert_eq!(top.get(18).unwrap().amount, 2);
+
+        client.refresh_top_backers(&0, &0, &50);
+        let top = client.get_top_backers(&0, &50);
+        assert_eq!(top.len(), 20);
+        assert_eq!(top.get(0).unwrap().amount, 20);
+        assert_eq!(top.get(19).unwrap().amount, 1);
+        assert_eq!(client.try_refresh_top_backers(&9, &0, &50), Err(Ok(Error::NotFound)));
+    }
+
+    fn no_rules() -> ContributionRules {
+        ContributionRules {
+            min_contribution: None,
+            max_per_contributor: None,
+            hard_cap: None,
+            allowlist: None,
+            kyc_verifier: None,
+        }
+    }
+
+    // Create a target-1000 campaign with the given options, surfacing validation errors
+    fn create_campaign_with_options(
+        env: &Env,
+        client: &CrowdfundingContractClient,
+        creator: &Address,
+        options: CampaignOptions,
+    ) -> Result<u32, Error> {
+        client
+            .try_create_campaign(
+                creator,
+                &String::from_str(env, "Test Campaign"),
+                &content_hash(env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &options,
+            )
+            .map(|index| index.unwrap())
+            .map_err(|error| error.unwrap())
+    }
+
+    // Stand-in for a KYC provider that vouches for the addresses it was told about
+    #[contract]
+    struct MockKycVerifier;
+
+    #[contractimpl]
+    impl MockKycVerifier {
+        pub fn verify(env: Env, address: Address) {
+            env.storage().persistent().set(&address, &true);
+        }
+
+        pub fn is_verified(env: Env, address: Address) -> bool {
+            env.storage().persistent().get(&address).unwrap_or(false)
+        }
+    }
+
+    #[test]
+    fn test_contribution_limits() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+
+        let creator = Address::random(&env);
+        let rules = ContributionRules {
+            min_contribution: Some(50),
+            max_per_contributor: Some(600),
+            hard_cap: Some(1100),
+            ..no_rules()
+        };
+        let campaign_options = CampaignOptions { rules, ..options(&env) };
+        let campaign_index = create_campaign_with_options(&env, &client, &creator, campaign_options).unwrap();
+
+        let contributor1 = Address::random(&env);
+        let contributor2 = Address::random(&env);
+        token_admin_client.mint(&contributor1, &1000);
+        token_admin_client.mint(&contributor2, &1000);
+
+        assert_eq!(
+            client.try_contribute(&contributor1, &campaign_index, &token, &49, &None),
+            Err(Ok(Error::BelowMinimum))
+        );
+        client.contribute(&contributor1, &campaign_index, &token, &500, &None);
+        assert_eq!(
+            client.try_contribute(&contributor1, &campaign_index, &token, &101, &None),
+            Err(Ok(Error::ExceedsContributorMax))
+        );
+        client.contribute(&contributor1, &campaign_index, &token, &100, &None);
+        client.contribute(&contributor2, &campaign_index, &token, &450, &None);
+        assert_eq!(
+            client.try_contribute(&contributor2, &campaign_index, &token, &51, &None),
+            Err(Ok(Error::HardCapReached))
+        );
+        client.contribute(&contributor2, &campaign_index, &token, &50, &None);
+        assert_eq!(client.get_campaign(&campaign_index).amount_raised, 1100);
+
+        // The target cannot be raised past the hard cap
+        let deadline = client.get_campaign(&campaign_index).deadline + 7 * 24 * 60 * 60;
+        assert_eq!(
+            client.try_update_campaign(&creator, &campaign_index, &1200, &deadline),
+            Err(Ok(Error::InvalidAmount))
+        );
+
+        // Rules that contradict each other or the target are rejected
+        let invalid = [
+            ContributionRules { min_contribution: Some(-1), ..no_rules() },
+            ContributionRules { min_contribution: Some(100), max_per_contributor: Some(99), ..no_rules() },
+            ContributionRules { max_per_contributor: Some(0), ..no_rules() },
+            ContributionRules { hard_cap: Some(999), ..no_rules() },
+        ];
+        for rules in invalid {
+            let campaign_options = CampaignOptions { rules, ..options(&env) };
+            assert_eq!(
+                create_campaign_with_options(&env, &client, &creator, campaign_options),
+                Err(Error::InvalidRules)
+            );
+        }
+    }
+
+    #[test]
+    fn test_allowlist_and_kyc_verifier() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+
+        let creator = Address::random(&env);
+        let approved = Address::random(&env);
+        let outsider = Address::random(&env);
+        token_admin_client.mint(&approved, &1000);
+        token_admin_client.mint(&outsider, &1000);
+
+        let rules = ContributionRules { allowlist: Some(vec![&env, approved.clone()]), ..no_rules() };
+        let campaign_options = CampaignOptions { rules, ..options(&env) };
+        let allowlisted = create_campaign_with_options(&env, &client, &creator, campaign_options).unwrap();
+        client.contribute(&approved, &allowlisted, &token, &100, &None);
+        assert_eq!(
+            client.try_contribute(&outsider, &allowlisted, &token, &100, &None),
+            Err(Ok(Error::NotAllowlisted))
+        );
+
+        // Only the creator manages the list
+        let addresses = vec![&env, outsider.clone()];
+        assert_eq!(
+            client.try_update_allowlist(&outsider, &allowlisted, &addresses, &true),
+            Err(Ok(Error::Unauthorized))
+        );
+        client.update_allowlist(&creator, &allowlisted, &addresses, &true);
+        assert!(client.is_allowlisted(&allowlisted, &outsider));
+        client.contribute(&outsider, &allowlisted, &token, &100, &None);
+        client.update_allowlist(&creator, &allowlisted, &vec![&env, approved.clone()], &false);
+        assert_eq!(
+            client.try_contribute(&approved, &allowlisted, &token, &100, &None),
+            Err(Ok(Error::NotAllowlisted))
+        );
+
+        let verifier_id = env.register_contract(None, MockKycVerifier);
+        let verifier = MockKycVerifierClient::new(&env, &verifier_id);
+        let rules = ContributionRules { kyc_verifier: Some(verifier_id.clone()), ..no_rules() };
+        let campaign_options = CampaignOptions { rules, ..options(&env) };
+        let verified = create_campaign_with_options(&env, &client, &creator, campaign_options).unwrap();
+        assert_eq!(
+            client.try_contribute(&approved, &verified, &token, &100, &None),
+            Err(Ok(Error::NotVerified))
+        );
+        verifier.verify(&approved);
+        client.contribute(&approved, &verified, &token, &100, &None);
+
+        // Campaigns without an allowlist have nothing to manage
+        assert_eq!(
+            client.try_update_allowlist(&creator, &verified, &addresses, &true),
+            Err(Ok(Error::InvalidRules))
+        );
+    }
+
+    #[test]
+    fn test_platform_fee_on_claim() {
+        let env = Env::default();
+        let (client, admin, token_client, token_admin_client) = setup_contract(&env);
+        let treasury = Address::random(&env);
+        let outsider = Address::random(&env);
+
+        // Without a fee configuration nothing is charged
+        let creator = Address::random(&env);
+        for _ in 0..3 {
+            client.create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &(env.ledger().timestamp() + 1000),
+                &options(&env),
+            );
+        }
+        assert_eq!(client.get_campaign_fee(&0), 0);
+        assert_eq!(client.try_get_fee_config(), Err(Ok(Error::NotFound)));
+
+        assert_eq!(client.try_set_fee(&outsider, &250, &treasury), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_set_fee(&admin, &1001, &treasury), Err(Ok(Error::InvalidFee)));
+        client.set_fee(&admin, &250, &treasury);
+
+        // Campaign 1 negotiated a lower rate, campaign 2 tried to exceed the ceiling
+        assert_eq!(client.try_set_campaign_fee(&outsider, &1, &Some(100)), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_set_campaign_fee(&admin, &2, &Some(1001)), Err(Ok(Error::InvalidFee)));
+        client.set_campaign_fee(&admin, &1, &Some(100));
+        client.set_campaign_fee(&admin, &2, &Some(0));
+        client.set_campaign_fee(&admin, &2, &None);
+        assert_eq!(client.get_campaign_fee(&0), 250);
+        assert_eq!(client.get_campaign_fee(&1), 100);
+        assert_eq!(client.get_campaign_fee(&2), 250);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &2000);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+        client.contribute(&contributor, &1, &token_client.address, &1000, &None);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+
+        client.claim(&creator, &0);
+        assert_eq!(token_client.balance(&creator), 975);
+        assert_eq!(token_client.balance(&treasury), 25);
+
+        // The payout event reports the net amount and the fee
+        let (_, _, data) = env.events().all().last().unwrap();
+        let (campaign_index, token, net, fee): (u32, Address, i128, i128) =
+            TryFromVal::try_from_val(&env, &data).unwrap();
+        assert_eq!((campaign_index, token, net, fee), (0, token_client.address.clone(), 975, 25));
+
+        client.claim(&creator, &1);
+        assert_eq!(token_client.balance(&creator), 975 + 990);
+        assert_eq!(token_client.balance(&treasury), 25 + 10);
+    }
+
+    #[test]
+    fn test_platform_fee_on_tranches() {
+        let env = Env::default();
+        let (client, admin, token_client, token_admin_client) = setup_contract(&env);
+        let treasury = Address::random(&env);
+        client.set_fee(&admin, &200, &treasury);
+
+        let creator = Address::random(&env);
+        client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Test Campaign"),
+            &content_hash(&env),
+            &1000,
+            &(env.ledger().timestamp() + 1000),
+            &options(&env),
+        );
+        let arbiter = Address::random(&env);
+        client.set_tranches(&creator, &0, &vec![&env, 5000, 5000], &Some(arbiter.clone()));
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token_client.address, &1000, &None);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+
+        // Each tranche is charged as it is released
+        for _ in 0..2 {
+            client.request_tranche(&creator, &0);
+            client.release_tranche(&arbiter, &0);
+        }
+        assert_eq!(token_client.balance(&creator), 980);
+        assert_eq!(token_client.balance(&treasury), 20);
+        assert_eq!(token_client.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_tranche_votes_ignore_matched_funds() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let token = token_client.address.clone();
+        client.set_tranches(&creator, &0, &vec![&env, 5000, 5000], &None);
+
+        let sponsor = Address::random(&env);
+        token_admin_client.mint(&sponsor, &1000);
+        client.create_match_pool(&sponsor, &token, &1000, &10_000, &1000, &vec![&env, 0]);
+
+        let backer1 = Address::random(&env);
+        let backer2 = Address::random(&env);
+        token_admin_client.mint(&backer1, &300);
+        token_admin_client.mint(&backer2, &200);
+        client.contribute(&backer1, &0, &token, &300, &None);
+        client.contribute(&backer2, &0, &token, &200, &None);
+        assert_eq!(client.get_campaign(&0).amount_raised, 1000);
+
+        // Backers hold 500 of the 1000 raised, so 300 is a majority of the votes that exist
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        client.request_tranche(&creator, &0);
+        client.approve_tranche(&backer1, &0);
+        client.release_tranche(&creator, &0);
+        assert_eq!(token_client.balance(&creator), 500);
+
+        client.request_tranche(&creator, &0);
+        client.approve_tranche(&backer2, &0);
+        assert_eq!(client.try_release_tranche(&creator, &0), Err(Ok(Error::TrancheNotApproved)));
+        client.vote_stop_tranches(&backer1, &0);
+        assert_eq!(count_events(&env, symbol_short!("TRANCHSTP")), 1);
+    }
+
+    #[test]
+    fn test_match_pool_draws_on_contribute() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+
+        let creator = Address::random(&env);
+        let deadline = env.ledger().timestamp() + 1000;
+        for _ in 0..2 {
+            client.create_campaign(
+                &creator,
+                &String::from_str(&env, "Test Campaign"),
+                &content_hash(&env),
+                &1000,
+                &deadline,
+                &CampaignOptions { allow_unpledge: true, ..options(&env) },
+            );
+        }
+
+        // One-for-one matching, at most 300 per campaign, from a 500 budget
+        let sponsor = Address::random(&env);
+        token_admin_client.mint(&sponsor, &500);
+        assert_eq!(
+            client.try_create_match_pool(&sponsor, &token, &500, &0, &300, &vec![&env, 0, 1]),
+            Err(Ok(Error::InvalidPool))
+        );
+        let pool_id = client.create_match_pool(&sponsor, &token, &500, &10_000, &300, &vec![&env, 0, 1]);
+        assert_eq!(token_client.balance(&sponsor), 0);
+        assert_eq!(
+            client.try_create_match_pool(&sponsor, &token, &500, &10_000, &300, &vec![&env, 1]),
+            Err(Ok(Error::CampaignAlreadyMatched))
+        );
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &2000);
+        client.contribute(&contributor, &0, &token, &200, &None);
+        assert_eq!(client.get_campaign(&0).amount_raised, 400);
+        assert_eq!(client.get_matched_amount(&0), 200);
+        assert_eq!(count_events(&env, symbol_short!("MATCHED")), 1);
+
+        // The per-campaign cap limits the second match
+        client.contribute(&contributor, &0, &token, &200, &None);
+        assert_eq!(client.get_campaign(&0).amount_raised, 700);
+        assert_eq!(client.get_matched_amount(&0), 300);
+
+        // Withdrawing a pledge gives its match back to the pool
+        client.unpledge(&contributor, &0, &token, &50);
+        assert_eq!(client.get_campaign(&0).amount_raised, 600);
+        assert_eq!(client.get_matched_amount(&0), 250);
+        assert_eq!(client.get_match_pool(&pool_id).remaining, 250);
+
+        // The budget runs out before campaign 1's cap
+        client.contribute(&contributor, &1, &token, &400, &None);
+        assert_eq!(client.get_matched_amount(&1), 250);
+        assert_eq!(client.get_campaign(&1).amount_raised, 650);
+        assert_eq!(client.get_match_pool(&pool_id).remaining, 0);
+
+        // A matched, successful campaign pays the creator everything raised
+        client.contribute(&contributor, &0, &token, &400, &None);
+        env.ledger().set_timestamp(deadline);
+        client.claim(&creator, &0);
+        assert_eq!(token_client.balance(&creator), 1000);
+    }
+
+    #[test]
+    fn test_match_pool_reclaim_after_deadline() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let token = token_client.address.clone();
+
+        let sponsor = Address::random(&env);
+        token_admin_client.mint(&sponsor, &1000);
+        let pool_id = client.create_match_pool(&sponsor, &token, &1000, &5_000, &1000, &vec![&env, 0]);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &1000);
+        client.contribute(&contributor, &0, &token, &400, &None);
+        assert_eq!(client.get_matched_amount(&0), 200);
+
+        assert_eq!(client.try_reclaim_match_pool(&sponsor, &pool_id), Err(Ok(Error::DeadlineNotPassed)));
+        env.ledger().set_timestamp(client.get_campaign(&0).deadline);
+        assert_eq!(client.try_reclaim_match_pool(&creator, &pool_id), Err(Ok(Error::Unauthorized)));
+
+        // The campaign missed its target, so the sponsor gets back the matched funds as well as the unused budget
+        assert_eq!(client.reclaim_match_pool(&sponsor, &pool_id), 1000);
+        assert_eq!(token_client.balance(&sponsor), 1000);
+        assert_eq!(client.try_reclaim_match_pool(&sponsor, &pool_id), Err(Ok(Error::NothingToRefund)));
+
+        // Contributors are refunded only what they put in
+        client.refund(&contributor, &0);
+        assert_eq!(token_client.balance(&contributor), 1000);
+        assert_eq!(token_client.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_match_pool_reclaim_waits_for_extended_deadline() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let token = token_client.address.clone();
+
+        let sponsor = Address::random(&env);
+        token_admin_client.mint(&sponsor, &1000);
+        let pool_id = client.create_match_pool(&sponsor, &token, &1000, &10_000, &1000, &vec![&env, 0]);
+
+        // Extending the campaign keeps the match on offer until the new deadline
+        let original_deadline = client.get_campaign(&0).deadline;
+        let extended_deadline = original_deadline + 300_000;
+        client.update_campaign(&creator, &0, &1000, &extended_deadline);
+        env.ledger().set_timestamp(original_deadline);
+        assert_eq!(client.try_reclaim_match_pool(&sponsor, &pool_id), Err(Ok(Error::DeadlineNotPassed)));
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &300);
+        client.contribute(&contributor, &0, &token, &300, &None);
+        assert_eq!(client.get_matched_amount(&0), 300);
+
+        env.ledger().set_timestamp(extended_deadline);
+        assert_eq!(client.reclaim_match_pool(&sponsor, &pool_id), 1000);
+    }
+
+    // Campaign 0 gets 100 from each of four backers, campaign 1 gets 400 from one whale. Both reach
+    // their target of 400 and end at now + 1000.
+    fn setup_round_campaigns(
+        env: &Env,
+        client: &CrowdfundingContractClient,
+        token_admin_client: &token::AdminClient,
+    ) -> (Address, [Address; 4], Address) {
+        let creator = Address::random(env);
+        for _ in 0..2 {
+            client.create_campaign(
+                &creator,
+                &String::from_str(env, "Public Good"),
+                &content_hash(env),
+                &400,
+                &(env.ledger().timestamp() + 1000),
+                &CampaignOptions { allow_unpledge: true, ..options(env) },
+            );
+        }
+
+        let backers: [Address; 4] = core::array::from_fn(|_| Address::random(env));
+        for backer in backers.iter() {
+            token_admin_client.mint(backer, &100);
+            client.contribute(backer, &0, &token_admin_client.address, &100, &None);
+        }
+        let whale = Address::random(env);
+        token_admin_client.mint(&whale, &400);
+        client.contribute(&whale, &1, &token_admin_client.address, &400, &None);
+
+        (creator, backers, whale)
+    }
+
+    #[test]
+    fn test_quadratic_funding_round() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+        let (creator, _backers, _whale) = setup_round_campaigns(&env, &client, &token_admin_client);
+
+        let operator = Address::random(&env);
+        let closes_at = env.ledger().timestamp() + 1000;
+        assert_eq!(
+            client.try_create_round(&operator, &token, &vec![&env, 0, 0], &closes_at, &None),
+            Err(Ok(Error::InvalidRound))
+        );
+        // The round can't close while its campaigns are still taking (and returning) contributions
+        assert_eq!(
+            client.try_create_round(&operator, &token, &vec![&env, 0, 1], &(closes_at - 1), &None),
+            Err(Ok(Error::InvalidDeadline))
+        );
+        let round_id = client.create_round(&operator, &token, &vec![&env, 0, 1], &closes_at, &None);
+
+        let funder = Address::random(&env);
+        token_admin_client.mint(&funder, &1000);
+        client.fund_round(&funder, &round_id, &1000);
+        assert_eq!(client.get_round(&round_id).pool, 1000);
+        assert_eq!(client.try_close_round(&round_id), Err(Ok(Error::DeadlineNotPassed)));
+
+        // Four small backers outweigh one large one: (4 * sqrt(100))^2 = 1600 against sqrt(400)^2 = 400
+        env.ledger().set_timestamp(closes_at);
+        assert_eq!(client.try_fund_round(&funder, &round_id, &1), Err(Ok(Error::DeadlinePassed)));
+        let allocations = client.close_round(&round_id);
+        assert_eq!(
+            allocations,
+            vec![
+                &env,
+                RoundAllocation { campaign_index: 0, score: 1600, amount: 800 },
+                RoundAllocation { campaign_index: 1, score: 400, amount: 200 },
+            ]
+        );
+        assert_eq!(client.get_round_allocations(&round_id), allocations);
+        assert_eq!(token_client.balance(&creator), 1000);
+        assert_eq!(count_events(&env, symbol_short!("QFALLOC")), 2);
+        assert_eq!(client.try_close_round(&round_id), Err(Ok(Error::RoundClosed)));
+
+        // The matching funds went to the creators, the campaigns' escrow is untouched
+        assert_eq!(token_client.balance(&client.address), 800);
+    }
+
+    #[test]
+    fn test_quadratic_funding_round_counts_only_verified_contributors() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+        let (_creator, backers, whale) = setup_round_campaigns(&env, &client, &token_admin_client);
+
+        let verifier_id = env.register_contract(None, MockKycVerifier);
+        let verifier = MockKycVerifierClient::new(&env, &verifier_id);
+        verifier.verify(&backers[0]);
+        verifier.verify(&whale);
+
+        let operator = Address::random(&env);
+        let closes_at = env.ledger().timestamp() + 1000;
+        let round_id = client.create_round(&operator, &token, &vec![&env, 0, 1], &closes_at, &Some(verifier_id));
+        token_admin_client.mint(&operator, &1000);
+        client.fund_round(&operator, &round_id, &1000);
+
+        // Unverified backers could be one person's sybils, so only one of campaign 0's backers counts
+        env.ledger().set_timestamp(closes_at);
+        assert_eq!(client.try_close_round(&round_id), Err(Ok(Error::TallyIncomplete)));
+        assert!(!client.tally_round(&round_id, &3));
+        assert!(client.tally_round(&round_id, &3));
+        let allocations = client.close_round(&round_id);
+        assert_eq!(allocations.get(0).unwrap(), RoundAllocation { campaign_index: 0, score: 100, amount: 200 });
+        assert_eq!(allocations.get(1).unwrap(), RoundAllocation { campaign_index: 1, score: 400, amount: 800 });
+    }
+
+    #[test]
+    fn test_verified_round_tallied_in_pages() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+        let deadline = env.ledger().timestamp() + 1000;
+        client.create_campaign(
+            &Address::random(&env),
+            &String::from_str(&env, "Public Good"),
+            &content_hash(&env),
+            &400,
+            &deadline,
+            &options(&env),
+        );
+
+        // 400 one-unit backers, every other one verified. Setting them up takes more than one
+        // transaction's budget; each tally and close call below is held to the default budget.
+        env.budget().reset_unlimited();
+        let verifier_id = env.register_contract(None, MockKycVerifier);
+        let verifier = MockKycVerifierClient::new(&env, &verifier_id);
+        for position in 0..400 {
+            let backer = Address::random(&env);
+            token_admin_client.mint(&backer, &1);
+            client.contribute(&backer, &0, &token, &1, &None);
+            if position % 2 == 0 {
+                verifier.verify(&backer);
+            }
+        }
+
+        let operator = Address::random(&env);
+        let round_id = client.create_round(&operator, &token, &vec![&env, 0], &deadline, &Some(verifier_id));
+        token_admin_client.mint(&operator, &1000);
+        client.fund_round(&operator, &round_id, &1000);
+
+        // Tallying can't start early, and takes one call per 50 contributors
+        assert_eq!(client.try_tally_round(&round_id, &50), Err(Ok(Error::DeadlineNotPassed)));
+        env.ledger().set_timestamp(deadline);
+        let mut calls = 0;
+        loop {
+            env.budget().reset_default();
+            calls += 1;
+            if client.tally_round(&round_id, &100) {
+                break;
+            }
+        }
+        assert_eq!(calls, 8);
+
+        env.budget().reset_default();
+        let allocations = client.close_round(&round_id);
+        let allocation = RoundAllocation { campaign_index: 0, score: 40_000, amount: 1000 };
+        assert_eq!(allocations.get(0).unwrap(), allocation);
+        assert_eq!(client.try_tally_round(&round_id, &50), Err(Ok(Error::RoundClosed)));
+    }
+
+    #[test]
+    fn test_quadratic_funding_round_without_contributions_refunds_operator() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let token = token_client.address.clone();
+
+        let operator = Address::random(&env);
+        let closes_at = env.ledger().timestamp() + 1000;
+        let round_id = client.create_round(&operator, &token, &vec![&env, 0], &closes_at, &None);
+        token_admin_client.mint(&operator, &300);
+        client.fund_round(&operator, &round_id, &300);
+
+        env.ledger().set_timestamp(closes_at);
+        client.close_round(&round_id);
+        assert_eq!(token_client.balance(&operator), 300);
+    }
+
+    #[test]
+    fn test_quadratic_funding_round_scores_only_successful_campaigns() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+        let (creator, backers, _whale) = setup_round_campaigns(&env, &client, &token_admin_client);
+
+        // Campaign 2 misses its target, so its backer will be refunded and it earns no match
+        let closes_at = env.ledger().timestamp() + 1000;
+        client.create_campaign(
+            &creator,
+            &String::from_str(&env, "Unfunded Good"),
+            &content_hash(&env),
+            &1000,
+            &closes_at,
+            &options(&env),
+        );
+        let backer = Address::random(&env);
+        token_admin_client.mint(&backer, &900);
+        client.contribute(&backer, &2, &token, &900, &None);
+
+        let operator = Address::random(&env);
+        let round_id = client.create_round(&operator, &token, &vec![&env, 0, 1, 2], &closes_at, &None);
+        token_admin_client.mint(&operator, &1001);
+        client.fund_round(&operator, &round_id, &1001);
+
+        // Rounding dust goes back to the operator rather than to a campaign
+        env.ledger().set_timestamp(closes_at);
+        let allocations = client.close_round(&round_id);
+        assert_eq!(allocations.get(2).unwrap(), RoundAllocation { campaign_index: 2, score: 0, amount: 0 });
+        assert_eq!(token_client.balance(&creator), 1000);
+        assert_eq!(token_client.balance(&operator), 1);
+
+        // Once the round has closed the pledges it scored can no longer be withdrawn
+        assert_eq!(client.try_unpledge(&backers[0], &0, &token, &100), Err(Ok(Error::DeadlinePassed)));
+        assert_eq!(client.get_user_contributions(&backers[0], &0), 100);
+        client.refund(&backer, &2);
+        assert_eq!(token_client.balance(&backer), 900);
+    }
+
+    #[test]
+    fn test_contribute_mints_receipt() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let backer = Address::random(&env);
+        token_admin_client.mint(&backer, &500);
+        client.contribute(&backer, &0, &token_client.address, &200, &None);
+        assert_eq!(count_events(&env, symbol_short!("RCPTMINT")), 1);
+        client.contribute(&backer, &0, &token_client.address, &100, &None);
+
+        // Topping up grows the existing receipt rather than minting another
+        let receipt_id = client.get_receipt_id(&0, &backer).unwrap();
+        assert_eq!(client.receipt_owner(&receipt_id), backer);
+        assert_eq!(
+            client.receipt_metadata(&receipt_id),
+            ReceiptMetadata {
+                id: receipt_id,
+                campaign_index: 0,
+                campaign_title: String::from_str(&env, "Test Campaign"),
+                content_hash: content_hash(&env),
+                owner: backer.clone(),
+                amount: 300,
+            }
+        );
+        assert_eq!(client.try_receipt_owner(&(receipt_id + 1)), Err(Ok(Error::NotFound)));
+
+        // Refunding burns the receipt
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        client.refund(&backer, &0);
+        assert_eq!(client.get_receipt_id(&0, &backer), None);
+        assert_eq!(client.try_receipt_owner(&receipt_id), Err(Ok(Error::NotFound)));
+        assert_eq!(count_events(&env, symbol_short!("RCPTBURN")), 1);
+    }
+
+    #[test]
+    fn test_receipt_transfer_moves_refund_claim() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let backer = Address::random(&env);
+        let other_backer = Address::random(&env);
+        let buyer = Address::random(&env);
+        token_admin_client.mint(&backer, &300);
+        token_admin_client.mint(&other_backer, &100);
+        client.contribute(&backer, &0, &token_client.address, &300, &None);
+        client.contribute(&other_backer, &0, &token_client.address, &100, &None);
+        let receipt_id = client.get_receipt_id(&0, &backer).unwrap();
+
+        // Only the holder can transfer, and never onto an address that already backs the campaign
+        assert_eq!(client.try_transfer_receipt(&buyer, &buyer, &receipt_id), Err(Ok(Error::Unauthorized)));
+        assert_eq!(
+            client.try_transfer_receipt(&backer, &other_backer, &receipt_id),
+            Err(Ok(Error::ReceiverHasPosition))
+        );
+
+        client.transfer_receipt(&backer, &buyer, &receipt_id);
+        assert_eq!(client.receipt_owner(&receipt_id), buyer);
+        assert_eq!(client.get_receipt_id(&0, &backer), None);
+        assert_eq!(client.get_user_contributions(&buyer, &0), 300);
+        assert_eq!(client.get_user_contributions(&backer, &0), 0);
+        assert_eq!(client.get_campaign(&0).amount_raised, 400);
+
+        // The refund is now paid to the new holder
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.try_refund(&backer, &0), Err(Ok(Error::NothingToRefund)));
+        client.refund(&buyer, &0);
+        assert_eq!(token_client.balance(&buyer), 300);
+        assert_eq!(token_client.balance(&backer), 0);
+        assert_eq!(client.try_receipt_owner(&receipt_id), Err(Ok(Error::NotFound)));
+    }
+
+    #[test]
+    fn test_receipt_transfer_respects_contribution_rules() {
+        let env = Env::default();
+        let (client, _admin, token_client, token_admin_client) = setup_contract(&env);
+        let token = token_client.address.clone();
+
+        let creator = Address::random(&env);
+        let approved = Address::random(&env);
+        let outsider = Address::random(&env);
+        token_admin_client.mint(&approved, &200);
+
+        // An allowlisted position can only go to another listed address
+        let rules = ContributionRules { allowlist: Some(vec![&env, approved.clone()]), ..no_rules() };
+        let campaign_options = CampaignOptions { rules, ..options(&env) };
+        let allowlisted = create_campaign_with_options(&env, &client, &creator, campaign_options).unwrap();
+        client.contribute(&approved, &allowlisted, &token, &100, &None);
+        let receipt_id = client.get_receipt_id(&allowlisted, &approved).unwrap();
+        assert_eq!(
+            client.try_transfer_receipt(&approved, &outsider, &receipt_id),
+            Err(Ok(Error::NotAllowlisted))
+        );
+        client.update_allowlist(&creator, &allowlisted, &vec![&env, outsider.clone()], &true);
+        client.transfer_receipt(&approved, &outsider, &receipt_id);
+        assert_eq!(client.get_user_contributions(&outsider, &allowlisted), 100);
+
+        // A KYC-gated position can only go to a verified address
+        let verifier_id = env.register_contract(None, MockKycVerifier);
+        let verifier = MockKycVerifierClient::new(&env, &verifier_id);
+        verifier.verify(&approved);
+        let rules = ContributionRules { kyc_verifier: Some(verifier_id), ..no_rules() };
+        let campaign_options = CampaignOptions { rules, ..options(&env) };
+        let verified = create_campaign_with_options(&env, &client, &creator, campaign_options).unwrap();
+        client.contribute(&approved, &verified, &token, &100, &None);
+        let receipt_id = client.get_receipt_id(&verified, &approved).unwrap();
+        let unverified = Address::random(&env);
+        assert_eq!(
+            client.try_transfer_receipt(&approved, &unverified, &receipt_id),
+            Err(Ok(Error::NotVerified))
+        );
+        assert_eq!(client.get_user_contributions(&approved, &verified), 100);
+        assert_eq!(client.get_user_contributions(&unverified, &verified), 0);
+    }
+
+    #[test]
+    fn test_all_or_nothing_refunds_below_target() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        assert_eq!(client.get_campaign(&0).funding_mode, FundingMode::AllOrNothing);
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &999);
+        client.contribute(&contributor, &0, &token_client.address, &999, &None);
+
+        // One short of the target is a failure
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.get_campaign(&0).status, CampaignStatus::Failed);
+        assert_eq!(client.try_claim(&creator, &0), Err(Ok(Error::TargetNotReached)));
+        client.refund(&contributor, &0);
+        assert_eq!(token_client.balance(&contributor), 999);
+    }
+
+    #[test]
+    fn test_keep_it_all_pays_out_below_target() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let keep_it_all = CampaignOptions { funding_mode: FundingMode::KeepItAll, ..options(&env) };
+        let kept = create_campaign_with_options(&env, &client, &creator, keep_it_all.clone()).unwrap();
+        let empty = create_campaign_with_options(&env, &client, &creator, keep_it_all).unwrap();
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &300);
+        client.contribute(&contributor, &kept, &token_client.address, &300, &None);
+
+        // Anything raised is kept, so there is nothing to refund
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.get_campaign(&kept).status, CampaignStatus::Successful);
+        assert_eq!(client.try_refund(&contributor, &kept), Err(Ok(Error::TargetReached)));
+        client.claim(&creator, &kept);
+        assert_eq!(token_client.balance(&creator), 300);
+
+        // A campaign that raised nothing has nothing to pay out
+        assert_eq!(client.get_campaign(&empty).status, CampaignStatus::Failed);
+        assert_eq!(client.try_claim(&creator, &empty), Err(Ok(Error::TargetNotReached)));
+    }
+
+    #[test]
+    fn test_keep_it_all_refunds_on_cancel() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let keep_it_all = CampaignOptions { funding_mode: FundingMode::KeepItAll, ..options(&env) };
+        let kept = create_campaign_with_options(&env, &client, &creator, keep_it_all).unwrap();
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &300);
+        client.contribute(&contributor, &kept, &token_client.address, &300, &None);
+
+        client.cancel_campaign(&creator, &kept);
+        client.refund(&contributor, &kept);
+        assert_eq!(token_client.balance(&contributor), 300);
+    }
+
+    #[test]
+    fn test_minimum_percent_threshold() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let half = CampaignOptions { funding_mode: FundingMode::MinimumPercent(50), ..options(&env) };
+        let below = create_campaign_with_options(&env, &client, &creator, half.clone()).unwrap();
+        let reached = create_campaign_with_options(&env, &client, &creator, half).unwrap();
+
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &999);
+        client.contribute(&contributor, &below, &token_client.address, &499, &None);
+        client.contribute(&contributor, &reached, &token_client.address, &500, &None);
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.get_campaign(&below).status, CampaignStatus::Failed);
+        assert_eq!(client.try_claim(&creator, &below), Err(Ok(Error::TargetNotReached)));
+        client.refund(&contributor, &below);
+        assert_eq!(token_client.balance(&contributor), 499);
+
+        // Half the target is enough, even though the full target was missed
+        assert_eq!(client.get_campaign(&reached).status, CampaignStatus::Successful);
+        assert_eq!(client.try_refund(&contributor, &reached), Err(Ok(Error::TargetReached)));
+        client.claim(&creator, &reached);
+        assert_eq!(token_client.balance(&creator), 500);
+    }
+
+    #[test]
+    fn test_invalid_funding_mode() {
+        let env = Env::default();
+        let (client, creator, _token_client, _token_admin_client) = setup_campaign(&env, 1000);
+
+        let minimum_percent = |percent| CampaignOptions {
+            funding_mode: FundingMode::MinimumPercent(percent),
+            ..options(&env)
+        };
+        for percent in [0, 101] {
+            assert_eq!(
+                create_campaign_with_options(&env, &client, &creator, minimum_percent(percent)),
+                Err(Error::InvalidCampaign)
+            );
+        }
+        assert!(create_campaign_with_options(&env, &client, &creator, minimum_percent(100)).is_ok());
+    }
+
+    #[test]
+    fn test_subscription_collects_installments() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+
+        let subscriber = Address::random(&env);
+        token_admin_client.mint(&subscriber, &1000);
+        let expiration_ledger = env.ledger().sequence() + 1000;
+        token_client.approve(&subscriber, &client.address, &250, &expiration_ledger);
+        let subscription_id = client.subscribe(&subscriber, &0, &token_client.address, &100, &100);
+
+        // The first installment is due straight away, and only one is taken per period
+        assert_eq!(client.process_subscriptions(&0, &10), 1);
+        assert_eq!(client.process_subscriptions(&0, &10), 0);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        assert_eq!(client.process_subscriptions(&0, &10), 1);
+        assert_eq!(client.get_user_contributions(&subscriber, &0), 200);
+        assert!(client.get_receipt_id(&0, &subscriber).is_some());
+
+        // The allowance only covers half of the third installment
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        assert_eq!(client.process_subscriptions(&0, &10), 0);
+        let subscription = client.get_subscription(&subscription_id);
+        assert_eq!(subscription.missed_payments, 1);
+        assert_eq!(subscription.installments_paid, 2);
+        assert!(subscription.active);
+
+        // Topping up the allowance resumes payments and clears the missed count
+        token_client.approve(&subscriber, &client.address, &100, &expiration_ledger);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        assert_eq!(client.process_subscriptions(&0, &10), 1);
+        assert_eq!(client.get_subscription(&subscription_id).missed_payments, 0);
+        assert_eq!(client.get_campaign(&0).amount_raised, 300);
+        assert_eq!(token_client.balance(&subscriber), 700);
+    }
+
+    #[test]
+    fn test_subscription_cancelled_after_missed_payments() {
+        let env = Env::default();
+        let (client, _creator, token_client, _token_admin_client) = setup_campaign(&env, 1000);
+
+        // No allowance was granted, so every installment fails
+        let subscriber = Address::random(&env);
+        let subscription_id = client.subscribe(&subscriber, &0, &token_client.address, &100, &100);
+        for _ in 0..3 {
+            assert_eq!(client.process_subscriptions(&0, &10), 0);
+            env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        }
+
+        let subscription = client.get_subscription(&subscription_id);
+        assert!(!subscription.active);
+        assert_eq!(subscription.missed_payments, 3);
+        assert_eq!(count_events(&env, symbol_short!("SUBMISSED")), 3);
+        assert_eq!(count_events(&env, symbol_short!("SUBCANCEL")), 1);
+        assert_eq!(client.get_user_contributions(&subscriber, &0), 0);
+    }
+
+    #[test]
+    fn test_cancel_subscription() {
+        let env = Env::default();
+        let (client, _creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let token = token_client.address.clone();
+
+        let subscriber = Address::random(&env);
+        token_admin_client.mint(&subscriber, &1000);
+        token_client.approve(&subscriber, &client.address, &1000, &(env.ledger().sequence() + 1000));
+        assert_eq!(client.try_subscribe(&subscriber, &0, &token, &0, &100), Err(Ok(Error::InvalidAmount)));
+        assert_eq!(client.try_subscribe(&subscriber, &0, &token, &100, &0), Err(Ok(Error::InvalidPeriod)));
+        let cancelled = client.subscribe(&subscriber, &0, &token, &100, &100);
+        let ended = client.subscribe(&subscriber, &0, &token, &50, &600);
+
+        // Only the subscriber can cancel, and only once
+        let stranger = Address::random(&env);
+        assert_eq!(client.try_cancel_subscription(&stranger, &cancelled), Err(Ok(Error::Unauthorized)));
+        client.cancel_subscription(&subscriber, &cancelled);
+        assert_eq!(
+            client.try_cancel_subscription(&subscriber, &cancelled),
+            Err(Ok(Error::SubscriptionInactive))
+        );
+        assert_eq!(client.try_cancel_subscription(&subscriber, &99), Err(Ok(Error::NotFound)));
+        assert_eq!(client.process_subscriptions(&0, &10), 1);
+        assert_eq!(client.get_user_contributions(&subscriber, &0), 50);
+
+        // The other subscription ends with the campaign instead of paying its second installment
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.process_subscriptions(&0, &10), 0);
+        assert!(!client.get_subscription(&ended).active);
+        assert_eq!(count_events(&env, symbol_short!("SUBENDED")), 1);
+        assert_eq!(client.get_user_contributions(&subscriber, &0), 50);
+    }
+
+    #[test]
+    fn test_update_metadata_keeps_history() {
+        let env = Env::default();
+        let (client, creator, token_client, token_admin_client) = setup_campaign(&env, 1000);
+        let created_at = env.ledger().timestamp();
+
+        let new_title = String::from_str(&env, "Renamed Campaign");
+        let new_hash = BytesN::from_array(&env, &[9; 32]);
+        let stranger = Address::random(&env);
+        assert_eq!(
+            client.try_update_metadata(&stranger, &0, &new_title, &new_hash),
+            Err(Ok(Error::Unauthorized))
+        );
+        let long_title = String::from_str(&env, &"x".repeat(65));
+        assert_eq!(
+            client.try_update_metadata(&creator, &0, &long_title, &new_hash),
+            Err(Ok(Error::InvalidCampaign))
+        );
+
+        env.ledger().set_timestamp(created_at + 100);
+        assert_eq!(client.update_metadata(&creator, &0, &new_title, &new_hash), 1);
+        assert_eq!(count_events(&env, symbol_short!("METAUPDT")), 1);
+        let campaign = client.get_campaign(&0);
+        assert_eq!(campaign.title, new_title);
+        assert_eq!(campaign.content_hash, new_hash);
+        assert_eq!(campaign.metadata_version, 1);
+
+        // The original version stays on record with the time it was replaced
+        let history = client.get_metadata_history(&0, &0, &10);
+        assert_eq!(history.len(), 2);
+        assert_eq!(client.get_metadata_history(&0, &1, &10).get(0).unwrap().version, 1);
+        assert_eq!(client.get_metadata_history(&0, &2, &10).len(), 0);
+        assert_eq!(client.try_get_metadata_history(&5, &0, &10), Err(Ok(Error::NotFound)));
+        assert_eq!(
+            history.get(0).unwrap(),
+            MetadataVersion {
+                version: 0,
+                title: String::from_str(&env, "Test Campaign"),
+                content_hash: content_hash(&env),
+                updated_at: created_at,
+            }
+        );
+        assert_eq!(
+            history.get(1).unwrap(),
+            MetadataVersion { version: 1, title: new_title, content_hash: new_hash, updated_at: created_at + 100 }
+        );
+
+        // Contributions record the metadata version the backer saw
+        let contributor = Address::random(&env);
+        token_admin_client.mint(&contributor, &100);
+        client.contribute(&contributor, &0, &token_client.address, &100, &None);
+        let (_, _, data) = env.events().all().last().unwrap();
+        let (campaign_index, _token, amount, version): (u32, Address, i128, u32) =
+            TryFromVal::try_from_val(&env, &data).unwrap();
+        assert_eq!((campaign_index, amount, version), (0, 100, 1));
     }
 }
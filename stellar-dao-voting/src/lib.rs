@@ -1,6 +1,5 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, symbol_short, vec, Env, Symbol, Address, Vec, Map, Val, IntoVal, BytesN};
-use soroban_sdk::auth::Context;
+use soroban_sdk::{contract, contracterror, contractimpl, symbol_short, token, vec, Env, Symbol, Address, Vec, Map, Val, IntoVal, BytesN, String};
 
 
 const PROPOSAL_CREATED: Symbol = symbol_short!("PROPCREAT");
@@ -14,30 +13,64 @@ const STATUS_PASSED: u32 = 2;
 const STATUS_FAILED: u32 = 3;
 const STATUS_EXECUTED: u32 = 4;
 
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    NotMember = 4,
+    NotFound = 5,
+    InvalidStatus = 6,
+    VotingEnded = 7,
+    VotingNotEnded = 8,
+    NoVotingPower = 9,
+}
+
+fn check_initialized(env: &Env) -> Result<(), Error> {
+    if env.storage().persistent().has(&symbol_short!("init")) {
+        Ok(())
+    } else {
+        Err(Error::NotInitialized)
+    }
+}
+
 #[contract]
 pub struct DaoVotingSystem;
 
 #[contractimpl]
 impl DaoVotingSystem {
    
-    pub fn initialize(env: Env, governance_token: Address, admin: Address, voting_period: u64) {
-        
+    pub fn initialize(env: Env, governance_token: Address, admin: Address, voting_period: u64) -> Result<(), Error> {
+        if env.storage().persistent().has(&symbol_short!("init")) {
+            return Err(Error::AlreadyInitialized);
+        }
+
         env.storage().persistent().set(&symbol_short!("gov_token"), &governance_token);
         env.storage().persistent().set(&symbol_short!("admin"), &admin);
-        env.storage().persistent().set(&symbol_short!("voting_period"), &voting_period);
-        env.storage().persistent().set(&symbol_short!("proposals"), &Vec::new(&env));
-        env.storage().persistent().set(&symbol_short!("members"), &Map::new(&env));
+        env.storage().persistent().set(&symbol_short!("period"), &voting_period);
+        env.storage().persistent().set(&symbol_short!("proposals"), &Vec::<Vec<Val>>::new(&env));
+        env.storage().persistent().set(&symbol_short!("members"), &Map::<Address, bool>::new(&env));
+        env.storage().persistent().set(&symbol_short!("init"), &true);
+
+        Ok(())
     }
 
     // Add a member with voting power (only admin)
-    pub fn add_member(env: Env, admin: Address, member: Address) {
+    pub fn add_member(env: Env, admin: Address, member: Address) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+
         // Verify the caller is the admin
         let stored_admin: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("admin"))
-            .unwrap();
-        assert!(admin == stored_admin, "Only admin can add members");
+            .ok_or(Error::NotInitialized)?;
+        if admin != stored_admin {
+            return Err(Error::Unauthorized);
+        }
 
         // Add member to the map
         let mut members: Map<Address, bool> = env
@@ -47,6 +80,8 @@ impl DaoVotingSystem {
             .unwrap_or_else(|| Map::new(&env));
         members.set(member, true);
         env.storage().persistent().set(&symbol_short!("members"), &members);
+
+        Ok(())
     }
 
     // Create a new proposal
@@ -56,43 +91,42 @@ impl DaoVotingSystem {
         title: String,
         description: String,
         action: BytesN<32>, 
-    ) -> u32 {
-        
+    ) -> Result<u32, Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
         let members: Map<Address, bool> = env
             .storage()
             .persistent()
             .get(&symbol_short!("members"))
             .unwrap_or_else(|| Map::new(&env));
-        assert!(members.get(creator.clone()).unwrap_or(false), "Only members can create proposals");
+        if !members.get(creator.clone()).unwrap_or(false) {
+            return Err(Error::NotMember);
+        }
 
-        let voting_period: u64 = env
+        let mut proposals: Vec<Vec<Val>> = env
             .storage()
             .persistent()
-            .get(&symbol_short!("voting_period"))
-            .unwrap();
+            .get(&symbol_short!("proposals"))
+            .unwrap_or_else(|| Vec::new(&env));
+        let proposal_id = proposals.len();
 
         let proposal = vec![
             &env,
+            proposal_id.into_val(&env),
             creator.clone().into_val(&env),
-            title.into_val(&env),
+            title.clone().into_val(&env),
             description.into_val(&env),
             action.into_val(&env),
             STATUS_PENDING.into_val(&env),
-            0i64.into_val(&env), 
-            0i64.into_val(&env), 
+            0i128.into_val(&env), 
+            0i128.into_val(&env), 
             env.ledger().timestamp().into_val(&env), 
             0u64.into_val(&env), 
             0u64.into_val(&env), 
         ];
 
-    
-        let mut proposals: Vec<Vec<Val>> = env
-            .storage()
-            .persistent()
-            .get(&symbol_short!("proposals"))
-            .unwrap_or_else(|| Vec::new(&env));
         proposals.push_back(proposal);
-        let proposal_id = proposals.len() - 1;
         env.storage().persistent().set(&symbol_short!("proposals"), &proposals);
 
         
@@ -101,25 +135,29 @@ impl DaoVotingSystem {
             (proposal_id, title),
         );
 
-        proposal_id
+        Ok(proposal_id)
     }
 
 
-    pub fn start_voting(env: Env, admin: Address, proposal_id: u32) {
-        
+    pub fn start_voting(env: Env, admin: Address, proposal_id: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+
         let stored_admin: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("admin"))
-            .unwrap();
-        assert!(admin == stored_admin, "Only admin can start voting");
+            .ok_or(Error::NotInitialized)?;
+        if admin != stored_admin {
+            return Err(Error::Unauthorized);
+        }
 
         
         let voting_period: u64 = env
             .storage()
             .persistent()
-            .get(&symbol_short!("voting_period"))
-            .unwrap();
+            .get(&symbol_short!("period"))
+            .ok_or(Error::NotInitialized)?;
 
         
         let mut proposals: Vec<Vec<Val>> = env
@@ -127,11 +165,15 @@ impl DaoVotingSystem {
             .persistent()
             .get(&symbol_short!("proposals"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(proposal_id < proposals.len(), "Proposal does not exist");
+        if proposal_id >= proposals.len() {
+            return Err(Error::NotFound);
+        }
 
         let mut proposal = proposals.get(proposal_id).unwrap();
         let status: u32 = proposal.get(5).unwrap().try_into().unwrap();
-        assert!(status == STATUS_PENDING, "Proposal must be pending to start voting");
+        if status != STATUS_PENDING {
+            return Err(Error::InvalidStatus);
+        }
 
         
         proposal.set(5, STATUS_ACTIVE.into_val(&env));
@@ -141,27 +183,35 @@ impl DaoVotingSystem {
         
         proposals.set(proposal_id, proposal);
         env.storage().persistent().set(&symbol_short!("proposals"), &proposals);
+
+        Ok(())
     }
 
 
-    pub fn vote(env: Env, voter: Address, proposal_id: u32, support: bool) {
-        
+    pub fn vote(env: Env, voter: Address, proposal_id: u32, support: bool) -> Result<(), Error> {
+        check_initialized(&env)?;
+        voter.require_auth();
+
         let members: Map<Address, bool> = env
             .storage()
             .persistent()
             .get(&symbol_short!("members"))
             .unwrap_or_else(|| Map::new(&env));
-        assert!(members.get(voter.clone()).unwrap_or(false), "Only members can vote");
+        if !members.get(voter.clone()).unwrap_or(false) {
+            return Err(Error::NotMember);
+        }
 
         
         let token_address: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("gov_token"))
-            .unwrap();
+            .ok_or(Error::NotInitialized)?;
         let token_client = token::Client::new(&env, &token_address);
         let voting_power = token_client.balance(&voter);
-        assert!(voting_power > 0, "Voter has no voting power");
+        if voting_power <= 0 {
+            return Err(Error::NoVotingPower);
+        }
 
         
         let mut proposals: Vec<Vec<Val>> = env
@@ -169,27 +219,30 @@ impl DaoVotingSystem {
             .persistent()
             .get(&symbol_short!("proposals"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(proposal_id < proposals.len(), "Proposal does not exist");
+        if proposal_id >= proposals.len() {
+            return Err(Error::NotFound);
+        }
 
         let mut proposal = proposals.get(proposal_id).unwrap();
         let status: u32 = proposal.get(5).unwrap().try_into().unwrap();
-        assert!(status == STATUS_ACTIVE, "Proposal must be active for voting");
+        if status != STATUS_ACTIVE {
+            return Err(Error::InvalidStatus);
+        }
 
         
-        let voting_end_at: u64 = proposal.get(10).unwrap().try_into().unwrap();
-        assert!(
-            env.ledger().timestamp() < voting_end_at,
-            "Voting period has ended"
-        );
+        let voting_end_at: u64 = proposal.get(10).unwrap().into_val(&env);
+        if env.ledger().timestamp() >= voting_end_at {
+            return Err(Error::VotingEnded);
+        }
 
 
         
         if support {
-            let mut yes_votes: i64 = proposal.get(6).unwrap().try_into().unwrap();
+            let mut yes_votes: i128 = proposal.get(6).unwrap().into_val(&env);
             yes_votes += voting_power;
             proposal.set(6, yes_votes.into_val(&env));
         } else {
-            let mut no_votes: i64 = proposal.get(7).unwrap().try_into().unwrap();
+            let mut no_votes: i128 = proposal.get(7).unwrap().into_val(&env);
             no_votes += voting_power;
             proposal.set(7, no_votes.into_val(&env));
         }
@@ -203,17 +256,24 @@ impl DaoVotingSystem {
             (VOTE_CAST, voter),
             (proposal_id, support, voting_power),
         );
+
+        Ok(())
     }
 
     // Execute a passed proposal
-    pub fn execute_proposal(env: Env, executor: Address, proposal_id: u32) {
+    pub fn execute_proposal(env: Env, executor: Address, proposal_id: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        executor.require_auth();
+
         // Verify the executor is a member
         let members: Map<Address, bool> = env
             .storage()
             .persistent()
             .get(&symbol_short!("members"))
             .unwrap_or_else(|| Map::new(&env));
-        assert!(members.get(executor.clone()).unwrap_or(false), "Only members can execute proposals");
+        if !members.get(executor.clone()).unwrap_or(false) {
+            return Err(Error::NotMember);
+        }
 
         // Get the proposal
         let mut proposals: Vec<Vec<Val>> = env
@@ -221,29 +281,32 @@ impl DaoVotingSystem {
             .persistent()
             .get(&symbol_short!("proposals"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(proposal_id < proposals.len(), "Proposal does not exist");
+        if proposal_id >= proposals.len() {
+            return Err(Error::NotFound);
+        }
 
         let mut proposal = proposals.get(proposal_id).unwrap();
         let status: u32 = proposal.get(5).unwrap().try_into().unwrap();
-        assert!(status == STATUS_ACTIVE || status == STATUS_PASSED, "Proposal must be active or passed");
+        if status != STATUS_ACTIVE && status != STATUS_PASSED {
+            return Err(Error::InvalidStatus);
+        }
 
         // Check voting period has ended
-        let voting_end_at: u64 = proposal.get(10).unwrap().try_into().unwrap();
-        assert!(
-            env.ledger().timestamp() >= voting_end_at,
-            "Voting period has not ended yet"
-        );
+        let voting_end_at: u64 = proposal.get(10).unwrap().into_val(&env);
+        if env.ledger().timestamp() < voting_end_at {
+            return Err(Error::VotingNotEnded);
+        }
 
         // vote counts
-        let yes_votes: i64 = proposal.get(6).unwrap().try_into().unwrap();
-        let no_votes: i64 = proposal.get(7).unwrap().try_into().unwrap();
+        let yes_votes: i128 = proposal.get(6).unwrap().into_val(&env);
+        let no_votes: i128 = proposal.get(7).unwrap().into_val(&env);
 
         // Determine if proposal passed (simple majority)
         if yes_votes > no_votes {
             proposal.set(5, STATUS_PASSED.into_val(&env));
             
             // Get the action to execute
-            let action: BytesN<32> = proposal.get(4).unwrap().try_into().unwrap();
+            let action: BytesN<32> = proposal.get(4).unwrap().into_val(&env);
 
             proposal.set(5, STATUS_EXECUTED.into_val(&env));
             
@@ -259,16 +322,22 @@ impl DaoVotingSystem {
     
         proposals.set(proposal_id, proposal);
         env.storage().persistent().set(&symbol_short!("proposals"), &proposals);
+
+        Ok(())
     }
 
 
-    pub fn get_proposal(env: Env, proposal_id: u32) -> Vec<Val> {
+    pub fn get_proposal(env: Env, proposal_id: u32) -> Result<Vec<Val>, Error> {
         let proposals: Vec<Vec<Val>> = env
             .storage()
             .persistent()
             .get(&symbol_short!("proposals"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(proposal_id < proposals.len(), "Proposal does not exist");
-        proposals.get(proposal_id).unwrap()
+        if proposal_id >= proposals.len() {
+            return Err(Error::NotFound);
+        }
+        Ok(proposals.get(proposal_id).unwrap())
     }
 }
+
+mod tests;
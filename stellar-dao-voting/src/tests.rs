@@ -1,60 +1,215 @@
 #[cfg(test)]
 mod test {
-    use soroban_sdk::{Env, Address, BytesN};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke},
+        token, Env, Address, BytesN, IntoVal, String,
+    };
 
-    use crate::{DaoVotingSystem, DaoVotingSystemClient};
+    use crate::{DaoVotingSystem, DaoVotingSystemClient, Error};
 
     #[test]
     fn test_dao_workflow() {
         let env = Env::default();
-        
+        env.mock_all_auths();
+
         // Create test governance token
         let token_address = env.register_stellar_asset_contract(Address::random(&env));
-        let token_client = token::Client::new(&env, &token_address);
-        
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
         // Deploy the DAO contract
         let contract_id = env.register_contract(None, DaoVotingSystem);
         let client = DaoVotingSystemClient::new(&env, &contract_id);
-        
+
         // Initialize the DAO with 1 week voting period
         let admin = Address::random(&env);
-        client.initialize(&token_address, &admin, 604800); // 7 days in seconds
-        
+        client.initialize(&token_address, &admin, &604800); // 7 days in seconds
+
         // Add members
         let member1 = Address::random(&env);
         let member2 = Address::random(&env);
         client.add_member(&admin, &member1);
         client.add_member(&admin, &member2);
-        
+
         // Give members some governance tokens
-        token_client.mint(&member1, &1000);
-        token_client.mint(&member2, &500);
-        
+        token_admin_client.mint(&member1, &1000);
+        token_admin_client.mint(&member2, &500);
+
         // Member creates a proposal
         let action = BytesN::from_array(&env, &[0; 32]); // Mock action
         let proposal_id = client.create_proposal(
             &member1,
-            "Upgrade contract".to_string(),
-            "Upgrade to version 2.0".to_string(),
-            action,
+            &String::from_str(&env, "Upgrade contract"),
+            &String::from_str(&env, "Upgrade to version 2.0"),
+            &action,
         );
-        
+
         // Admin starts voting
-        client.start_voting(&admin, proposal_id);
-        
+        client.start_voting(&admin, &proposal_id);
+
         // Members vote
-        client.vote(&member1, proposal_id, true); // Yes vote with 1000 power
-        client.vote(&member2, proposal_id, false); // No vote with 500 power
-        
+        client.vote(&member1, &proposal_id, &true); // Yes vote with 1000 power
+        client.vote(&member2, &proposal_id, &false); // No vote with 500 power
+
         // Fast forward time to end voting period
         env.ledger().set_timestamp(env.ledger().timestamp() + 604800);
-        
+
         // Execute the proposal
-        client.execute_proposal(&member1, proposal_id);
-        
+        client.execute_proposal(&member1, &proposal_id);
+
         // Verify proposal status is executed
-        let proposal = client.get_proposal(proposal_id);
+        let proposal = client.get_proposal(&proposal_id);
         let status: u32 = proposal.get(5).unwrap().try_into().unwrap();
         assert_eq!(status, 4); // STATUS_EXECUTED
     }
+
+    #[test]
+    fn test_unauthorized_calls_fail() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
+        let contract_id = env.register_contract(None, DaoVotingSystem);
+        let client = DaoVotingSystemClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let member = Address::random(&env);
+        let attacker = Address::random(&env);
+        client.initialize(&token_address, &admin, &604800);
+        client.add_member(&admin, &member);
+        token_admin_client.mint(&member, &1000);
+
+        // Passing the admin's address without the admin's signature is rejected
+        let new_member = Address::random(&env);
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "add_member",
+                args: (&admin, &new_member).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_add_member(&admin, &new_member).is_err());
+
+        // A proposal needs the creating member's signature
+        let title = String::from_str(&env, "Drain treasury");
+        let description = String::from_str(&env, "Send everything to the attacker");
+        let action = BytesN::from_array(&env, &[1; 32]);
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "create_proposal",
+                args: (&member, title.clone(), description.clone(), action.clone()).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_create_proposal(&member, &title, &description, &action).is_err());
+
+        env.mock_all_auths();
+        let proposal_id = client.create_proposal(&member, &title, &description, &action);
+        client.start_voting(&admin, &proposal_id);
+
+        // A vote needs the voter's signature
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "vote",
+                args: (&member, proposal_id, true).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_vote(&member, &proposal_id, &true).is_err());
+
+        // The same vote signed by the member goes through
+        env.mock_auths(&[MockAuth {
+            address: &member,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "vote",
+                args: (&member, proposal_id, true).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        client.vote(&member, &proposal_id, &true);
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
+        let contract_id = env.register_contract(None, DaoVotingSystem);
+        let client = DaoVotingSystemClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let member = Address::random(&env);
+        let poor_member = Address::random(&env);
+        let outsider = Address::random(&env);
+        client.initialize(&token_address, &admin, &1000);
+
+        // Only the stored admin can manage members
+        assert_eq!(client.try_add_member(&outsider, &outsider), Err(Ok(Error::Unauthorized)));
+        client.add_member(&admin, &member);
+        client.add_member(&admin, &poor_member);
+        token_admin_client.mint(&member, &1000);
+
+        let title = String::from_str(&env, "Proposal");
+        let description = String::from_str(&env, "Description");
+        let action = BytesN::from_array(&env, &[0; 32]);
+        assert_eq!(
+            client.try_create_proposal(&outsider, &title, &description, &action),
+            Err(Ok(Error::NotMember))
+        );
+        let proposal_id = client.create_proposal(&member, &title, &description, &action);
+
+        assert_eq!(client.try_get_proposal(&5), Err(Ok(Error::NotFound)));
+        assert_eq!(client.try_start_voting(&admin, &5), Err(Ok(Error::NotFound)));
+        assert_eq!(client.try_vote(&member, &proposal_id, &true), Err(Ok(Error::InvalidStatus)));
+
+        client.start_voting(&admin, &proposal_id);
+        assert_eq!(client.try_start_voting(&admin, &proposal_id), Err(Ok(Error::InvalidStatus)));
+        assert_eq!(client.try_vote(&outsider, &proposal_id, &true), Err(Ok(Error::NotMember)));
+        assert_eq!(client.try_vote(&poor_member, &proposal_id, &true), Err(Ok(Error::NoVotingPower)));
+        assert_eq!(
+            client.try_execute_proposal(&member, &proposal_id),
+            Err(Ok(Error::VotingNotEnded))
+        );
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1000);
+        assert_eq!(client.try_vote(&member, &proposal_id, &true), Err(Ok(Error::VotingEnded)));
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let contract_id = env.register_contract(None, DaoVotingSystem);
+        let client = DaoVotingSystemClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let member = Address::random(&env);
+
+        // Nothing works before initialization
+        assert_eq!(client.try_add_member(&admin, &member), Err(Ok(Error::NotInitialized)));
+
+        client.initialize(&token_address, &admin, &1000);
+
+        // A second initialize cannot replace the admin
+        let attacker = Address::random(&env);
+        assert_eq!(
+            client.try_initialize(&token_address, &attacker, &1000),
+            Err(Ok(Error::AlreadyInitialized))
+        );
+        assert_eq!(client.try_add_member(&attacker, &attacker), Err(Ok(Error::Unauthorized)));
+        client.add_member(&admin, &member);
+    }
 }
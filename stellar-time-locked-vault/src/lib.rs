@@ -1,52 +1,81 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, symbol_short, vec, Env, Symbol, Address, BytesN, Val, IntoVal, token};
-use soroban_sdk::auth::{Context, CustomAccountInterface};
-use soroban_sdk::xdr::{ScVal, ScVec};
+use soroban_sdk::{contract, contracterror, contractimpl, symbol_short, vec, Env, Symbol, Address, Vec, Map, Val, IntoVal, token};
 
 
 const DEPOSIT_EVENT: Symbol = symbol_short!("DEPOSIT");
 const WITHDRAWAL_EVENT: Symbol = symbol_short!("WITHDRAW");
 const BENEFICIARY_ADDED: Symbol = symbol_short!("BENADDED");
 
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    NotFound = 4,
+    InvalidAmount = 5,
+    InvalidUnlockTime = 6,
+    StillLocked = 7,
+}
+
+fn check_initialized(env: &Env) -> Result<(), Error> {
+    if env.storage().persistent().has(&symbol_short!("init")) {
+        Ok(())
+    } else {
+        Err(Error::NotInitialized)
+    }
+}
+
 #[contract]
 pub struct TimeLockedVault;
 
 #[contractimpl]
 impl TimeLockedVault {
    
-    pub fn initialize(env: Env, token_address: Address, admin: Address) {
-        
+    pub fn initialize(env: Env, token_address: Address, admin: Address) -> Result<(), Error> {
+        if env.storage().persistent().has(&symbol_short!("init")) {
+            return Err(Error::AlreadyInitialized);
+        }
+
         env.storage().persistent().set(&symbol_short!("token"), &token_address);
         env.storage().persistent().set(&symbol_short!("admin"), &admin);
         
        
-        env.storage().persistent().set(&symbol_short!("deposits"), &Vec::new(&env));
-        env.storage().persistent().set(&symbol_short!("beneficiaries"), &Map::new(&env));
+        env.storage().persistent().set(&symbol_short!("deposits"), &Vec::<Vec<Val>>::new(&env));
+        env.storage().persistent().set(&symbol_short!("benefics"), &Map::<u32, Address>::new(&env));
+        env.storage().persistent().set(&symbol_short!("init"), &true);
+
+        Ok(())
     }
 
     // Deposit tokens into the vault with a lock period
     pub fn deposit(
         env: Env,
         from: Address,
-        amount: i64,
+        amount: i128,
         unlock_timestamp: u64,
-    ) {
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        from.require_auth();
+
         // Verify the amount is positive
-        assert!(amount > 0, "Amount must be positive");
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
         
         // Verify the unlock timestamp is in the future
         let current_timestamp = env.ledger().timestamp();
-        assert!(
-            unlock_timestamp > current_timestamp,
-            "Unlock timestamp must be in the future"
-        );
+        if unlock_timestamp <= current_timestamp {
+            return Err(Error::InvalidUnlockTime);
+        }
 
         // Get the token client
         let token_address: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("token"))
-            .unwrap();
+            .ok_or(Error::NotInitialized)?;
         let token_client = token::Client::new(&env, &token_address);
 
         // Transfer tokens from the sender to the contract
@@ -55,7 +84,7 @@ impl TimeLockedVault {
         // Create deposit record
         let deposit = vec![
             &env,
-            from.clone(),
+            from.clone().into_val(&env),
             amount.into_val(&env),
             unlock_timestamp.into_val(&env),
         ];
@@ -74,6 +103,8 @@ impl TimeLockedVault {
             (DEPOSIT_EVENT, from),
             (amount, unlock_timestamp),
         );
+
+        Ok(())
     }
 
   
@@ -82,14 +113,19 @@ impl TimeLockedVault {
         admin: Address,
         beneficiary: Address,
         deposit_index: u32,
-    ) {
+    ) -> Result<(), Error> {
+        check_initialized(&env)?;
+        admin.require_auth();
+
         // Verify the caller is the admin
         let stored_admin: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("admin"))
-            .unwrap();
-        assert!(admin == stored_admin, "Only admin can add beneficiaries");
+            .ok_or(Error::NotInitialized)?;
+        if admin != stored_admin {
+            return Err(Error::Unauthorized);
+        }
 
      
         let deposits: Vec<Vec<Val>> = env
@@ -97,68 +133,68 @@ impl TimeLockedVault {
             .persistent()
             .get(&symbol_short!("deposits"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(
-            deposit_index < deposits.len(),
-            "Deposit does not exist"
-        );
+        if deposit_index >= deposits.len() {
+            return Err(Error::NotFound);
+        }
 
         let mut beneficiaries: Map<u32, Address> = env
             .storage()
             .persistent()
-            .get(&symbol_short!("beneficiaries"))
+            .get(&symbol_short!("benefics"))
             .unwrap_or_else(|| Map::new(&env));
         beneficiaries.set(deposit_index, beneficiary.clone());
-        env.storage().persistent().set(&symbol_short!("beneficiaries"), &beneficiaries);
+        env.storage().persistent().set(&symbol_short!("benefics"), &beneficiaries);
 
         // Emit event
         env.events().publish(
             (BENEFICIARY_ADDED, admin),
             (deposit_index, beneficiary),
         );
+
+        Ok(())
     }
 
-    pub fn withdraw(env: Env, beneficiary: Address, deposit_index: u32) {
-       
+    pub fn withdraw(env: Env, beneficiary: Address, deposit_index: u32) -> Result<(), Error> {
+        check_initialized(&env)?;
+        beneficiary.require_auth();
+
         let mut deposits: Vec<Vec<Val>> = env
             .storage()
             .persistent()
             .get(&symbol_short!("deposits"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(
-            deposit_index < deposits.len(),
-            "Deposit does not exist"
-        );
+        if deposit_index >= deposits.len() {
+            return Err(Error::NotFound);
+        }
         let deposit = deposits.get(deposit_index).unwrap();
 
    
         let beneficiaries: Map<u32, Address> = env
             .storage()
             .persistent()
-            .get(&symbol_short!("beneficiaries"))
+            .get(&symbol_short!("benefics"))
             .unwrap_or_else(|| Map::new(&env));
-        let authorized_beneficiary = beneficiaries.get(deposit_index).unwrap();
-        assert!(
-            beneficiary == authorized_beneficiary,
-            "Not authorized to withdraw this deposit"
-        );
+        let authorized_beneficiary = beneficiaries.get(deposit_index).ok_or(Error::Unauthorized)?;
+        if beneficiary != authorized_beneficiary {
+            return Err(Error::Unauthorized);
+        }
 
-        let unlock_timestamp: u64 = deposit.get(3).unwrap().try_into().unwrap();
+        let unlock_timestamp: u64 = deposit.get(2).unwrap().into_val(&env);
         let current_timestamp = env.ledger().timestamp();
-        assert!(
-            current_timestamp >= unlock_timestamp,
-            "Lock period has not passed yet"
-        );
+        if current_timestamp < unlock_timestamp {
+            return Err(Error::StillLocked);
+        }
 
        
         let token_address: Address = env
             .storage()
             .persistent()
             .get(&symbol_short!("token"))
-            .unwrap();
+            .ok_or(Error::NotInitialized)?;
         let token_client = token::Client::new(&env, &token_address);
 
         
-        let amount: i64 = deposit.get(2).unwrap().try_into().unwrap();
+        let amount: i128 = deposit.get(1).unwrap().into_val(&env);
 
       
         token_client.transfer(
@@ -175,38 +211,41 @@ impl TimeLockedVault {
         let mut beneficiaries: Map<u32, Address> = env
             .storage()
             .persistent()
-            .get(&symbol_short!("beneficiaries"))
+            .get(&symbol_short!("benefics"))
             .unwrap_or_else(|| Map::new(&env));
         beneficiaries.remove(deposit_index);
-        env.storage().persistent().set(&symbol_short!("beneficiaries"), &beneficiaries);
+        env.storage().persistent().set(&symbol_short!("benefics"), &beneficiaries);
 
     
         env.events().publish(
             (WITHDRAWAL_EVENT, beneficiary),
             (deposit_index, amount),
         );
+
+        Ok(())
     }
 
-    pub fn get_deposit(env: Env, deposit_index: u32) -> Vec<Val> {
+    pub fn get_deposit(env: Env, deposit_index: u32) -> Result<Vec<Val>, Error> {
         let deposits: Vec<Vec<Val>> = env
             .storage()
             .persistent()
             .get(&symbol_short!("deposits"))
             .unwrap_or_else(|| Vec::new(&env));
-        assert!(
-            deposit_index < deposits.len(),
-            "Deposit does not exist"
-        );
-        deposits.get(deposit_index).unwrap()
+        if deposit_index >= deposits.len() {
+            return Err(Error::NotFound);
+        }
+        Ok(deposits.get(deposit_index).unwrap())
     }
 
 
-    pub fn get_beneficiary(env: Env, deposit_index: u32) -> Address {
+    pub fn get_beneficiary(env: Env, deposit_index: u32) -> Result<Address, Error> {
         let beneficiaries: Map<u32, Address> = env
             .storage()
             .persistent()
-            .get(&symbol_short!("beneficiaries"))
+            .get(&symbol_short!("benefics"))
             .unwrap_or_else(|| Map::new(&env));
-        beneficiaries.get(deposit_index).unwrap()
+        beneficiaries.get(deposit_index).ok_or(Error::NotFound)
     }
 }
+
+mod tests;
@@ -1,46 +1,168 @@
 #[cfg(test)]
 mod test {
-    use soroban_sdk::{Env, Address, BytesN};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke},
+        token, Env, Address, IntoVal,
+    };
 
-    use crate::{TimeLockedVault, TimeLockedVaultClient};
+    use crate::{Error, TimeLockedVault, TimeLockedVaultClient};
 
     #[test]
     fn test_deposit_and_withdraw() {
         let env = Env::default();
-        
+        env.mock_all_auths();
+
         // Create test token
         let token_address = env.register_stellar_asset_contract(Address::random(&env));
         let token_client = token::Client::new(&env, &token_address);
-        
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
         // Deploy the vault contract
         let contract_id = env.register_contract(None, TimeLockedVault);
         let client = TimeLockedVaultClient::new(&env, &contract_id);
-        
+
         // Initialize the vault
         let admin = Address::random(&env);
         client.initialize(&token_address, &admin);
-        
+
         // Mint some test tokens to a user
         let user = Address::random(&env);
-        token_client.mint(&user, &1000);
-        
+        token_admin_client.mint(&user, &1000);
+
         // User deposits tokens with 1000 seconds lock
         let unlock_time = env.ledger().timestamp() + 1000;
-        client.deposit(&user, 500, unlock_time);
-        
+        client.deposit(&user, &500, &unlock_time);
+
         // Admin adds beneficiary
         let beneficiary = Address::random(&env);
-        client.add_beneficiary(&admin, &beneficiary, 0);
-        
+        client.add_beneficiary(&admin, &beneficiary, &0);
+
         // Try to withdraw too early (should fail)
         env.ledger().set_timestamp(unlock_time - 1);
-        assert!(env.try_invoke_contract::<()>(&contract_id, "withdraw", (beneficiary.clone(), 0)).is_err());
-        
+        assert_eq!(client.try_withdraw(&beneficiary, &0), Err(Ok(Error::StillLocked)));
+
         // Wait until unlock time and withdraw
         env.ledger().set_timestamp(unlock_time);
-        client.withdraw(&beneficiary, 0);
-        
-    
+        client.withdraw(&beneficiary, &0);
+
+
         assert_eq!(token_client.balance(&beneficiary), 500);
     }
+
+    #[test]
+    fn test_unauthorized_calls_fail() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
+        let contract_id = env.register_contract(None, TimeLockedVault);
+        let client = TimeLockedVaultClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let user = Address::random(&env);
+        let beneficiary = Address::random(&env);
+        let attacker = Address::random(&env);
+        client.initialize(&token_address, &admin);
+        token_admin_client.mint(&user, &1000);
+
+        let unlock_time = env.ledger().timestamp() + 1000;
+        client.deposit(&user, &500, &unlock_time);
+
+        // Passing the admin's address without the admin's signature is rejected
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "add_beneficiary",
+                args: (&admin, &attacker, 0u32).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_add_beneficiary(&admin, &attacker, &0).is_err());
+
+        env.mock_all_auths();
+        client.add_beneficiary(&admin, &beneficiary, &0);
+        env.ledger().set_timestamp(unlock_time);
+
+        // Withdrawing on behalf of the beneficiary needs the beneficiary's signature
+        env.mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "withdraw",
+                args: (&beneficiary, 0u32).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_withdraw(&beneficiary, &0).is_err());
+
+        env.mock_auths(&[MockAuth {
+            address: &beneficiary,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "withdraw",
+                args: (&beneficiary, 0u32).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        client.withdraw(&beneficiary, &0);
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let token_admin_client = token::AdminClient::new(&env, &token_address);
+
+        let contract_id = env.register_contract(None, TimeLockedVault);
+        let client = TimeLockedVaultClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let user = Address::random(&env);
+        let outsider = Address::random(&env);
+        client.initialize(&token_address, &admin);
+        token_admin_client.mint(&user, &1000);
+
+        let now = env.ledger().timestamp();
+        assert_eq!(client.try_deposit(&user, &0, &(now + 1000)), Err(Ok(Error::InvalidAmount)));
+        assert_eq!(client.try_deposit(&user, &500, &now), Err(Ok(Error::InvalidUnlockTime)));
+        client.deposit(&user, &500, &(now + 1000));
+
+        assert_eq!(client.try_get_deposit(&1), Err(Ok(Error::NotFound)));
+        assert_eq!(client.try_get_beneficiary(&0), Err(Ok(Error::NotFound)));
+        assert_eq!(client.try_add_beneficiary(&outsider, &outsider, &0), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_add_beneficiary(&admin, &outsider, &1), Err(Ok(Error::NotFound)));
+
+        // No beneficiary has been assigned yet
+        env.ledger().set_timestamp(now + 1000);
+        assert_eq!(client.try_withdraw(&outsider, &0), Err(Ok(Error::Unauthorized)));
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let token_address = env.register_stellar_asset_contract(Address::random(&env));
+        let contract_id = env.register_contract(None, TimeLockedVault);
+        let client = TimeLockedVaultClient::new(&env, &contract_id);
+
+        let admin = Address::random(&env);
+        let user = Address::random(&env);
+
+        // Nothing works before initialization
+        let unlock_time = env.ledger().timestamp() + 1000;
+        assert_eq!(client.try_deposit(&user, &500, &unlock_time), Err(Ok(Error::NotInitialized)));
+
+        client.initialize(&token_address, &admin);
+
+        // A second initialize cannot replace the admin
+        let attacker = Address::random(&env);
+        assert_eq!(client.try_initialize(&token_address, &attacker), Err(Ok(Error::AlreadyInitialized)));
+        assert_eq!(client.try_add_beneficiary(&attacker, &attacker, &0), Err(Ok(Error::Unauthorized)));
+    }
 }